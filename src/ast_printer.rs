@@ -10,18 +10,18 @@ impl Visitor<String, ()> for AstPrinter {
                 ref left,
                 operator,
                 ref right,
-            } => self.parenthesize(operator.lexeme, &[left, right]),
+            } => self.parenthesize(&operator.lexeme, &[left, right]),
             Expr::Grouping(ref expr) => self.parenthesize("group", &[expr]),
             Expr::Literal(literal) => literal.to_string(),
             Expr::Unary {
                 operator,
                 ref right,
-            } => self.parenthesize(operator.lexeme, &[right]),
+            } => self.parenthesize(&operator.lexeme, &[right]),
             _ => todo!(),
         }
     }
 
-    fn visit_stmt(&mut self, stmt: &Stmt) {
+    fn visit_stmt(&mut self, _stmt: &Stmt) {
         panic!()
     }
 }
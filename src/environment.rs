@@ -1,12 +1,14 @@
-use crate::error::{Error, LoxResult};
+use crate::error::{Error, LoxResult, RuntimeError};
 use crate::token::{Literal, Token};
-use std::cell::{Ref, RefCell};
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 
 pub struct Environment {
     enclosing: Option<Rc<RefCell<Environment>>>,
     values: HashMap<String, Literal>,
+    // Names in `values` declared with `const`
+    constants: HashSet<String>,
 }
 
 impl Environment {
@@ -14,6 +16,7 @@ impl Environment {
         Self {
             enclosing: None,
             values: HashMap::new(),
+            constants: HashSet::new(),
         }
     }
 
@@ -21,41 +24,115 @@ impl Environment {
         Self {
             enclosing: Some(enclosing),
             values: HashMap::new(),
+            constants: HashSet::new(),
         }
     }
 
-    pub fn define(&mut self, name: String, value: Literal) {
+    // Declaring a name again replaces it, unless it was declared `const`
+    pub fn define(&mut self, name: &Token, value: Literal) -> LoxResult<()> {
+        self.check_redeclaration(name)?;
+        self.values.insert(name.lexeme.to_string(), value);
+        Ok(())
+    }
+
+    pub fn define_constant(&mut self, name: &Token, value: Literal) -> LoxResult<()> {
+        self.check_redeclaration(name)?;
+        self.constants.insert(name.lexeme.to_string());
+        self.values.insert(name.lexeme.to_string(), value);
+        Ok(())
+    }
+
+    // For names the interpreter binds by itself, such as `this`, `super` and
+    // the builtins, which no declaration can have made constant
+    pub fn define_implicit(&mut self, name: String, value: Literal) {
         self.values.insert(name, value);
     }
 
-    // The RunTime error needs to be changed to hold token
-    // in order to be able to tell exactly where in code the
-    // error occurred
-    pub fn get<'a>(&self, name: &Token) -> LoxResult<'a, Literal> {
-        match self.values.get(name.lexeme) {
+    fn check_redeclaration(&self, name: &Token) -> LoxResult<()> {
+        if self.constants.contains(&*name.lexeme) {
+            return Err(Error::RunTime(RuntimeError {
+                token: name.clone(),
+                message: format!("Can't redeclare constant '{}'.", name.lexeme),
+            }));
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, name: &Token) -> LoxResult<Literal> {
+        match self.values.get(&*name.lexeme) {
             Some(v) => Ok(v.clone()),
             None => {
                 if let Some(enclosing) = &self.enclosing {
                     return enclosing.borrow().get(name);
                 }
-                Err(Error::RunTime(format!(
-                    "Undefined variable: {}.",
-                    name.lexeme,
-                )))
+                Err(undefined_variable(name))
             }
         }
     }
 
-    pub fn assign<'a>(&mut self, name: String, value: Literal) -> LoxResult<'a, ()> {
-        match self.values.contains_key(&name) {
-            true => self.define(name, value),
+    pub fn assign(&mut self, name: &Token, value: Literal) -> LoxResult<()> {
+        match self.values.contains_key(&*name.lexeme) {
+            true => self.assign_here(name, value)?,
             false => {
                 if let Some(enclosing) = &mut self.enclosing {
                     return enclosing.borrow_mut().assign(name, value);
                 }
-                return Err(Error::RunTime(format!("Undefined variable: {}.", name)));
+                return Err(undefined_variable(name));
             }
         }
         Ok(())
     }
+
+    // The environment right below the outermost one of the chain, which
+    // holds the globals of the script or module the code was declared in.
+    // The outermost one holds the builtins, which globals can shadow.
+    pub fn globals(environment: &Rc<RefCell<Environment>>) -> Rc<RefCell<Environment>> {
+        let mut current = Rc::clone(environment);
+        loop {
+            let enclosing = current.borrow().enclosing.clone();
+            match enclosing {
+                Some(enclosing) if enclosing.borrow().enclosing.is_some() => current = enclosing,
+                _ => return current,
+            }
+        }
+    }
+
+    // Looks `name` up exactly `distance` environments up the chain,
+    // as computed by the resolver.
+    pub fn get_at(&self, distance: usize, name: &str) -> Option<Literal> {
+        if distance == 0 {
+            return self.values.get(name).cloned();
+        }
+        self.enclosing
+            .as_ref()
+            .and_then(|enclosing| enclosing.borrow().get_at(distance - 1, name))
+    }
+
+    pub fn assign_at(&mut self, distance: usize, name: &Token, value: Literal) -> LoxResult<()> {
+        if distance == 0 {
+            return self.assign_here(name, value);
+        }
+        match &self.enclosing {
+            Some(enclosing) => enclosing.borrow_mut().assign_at(distance - 1, name, value),
+            None => Err(undefined_variable(name)),
+        }
+    }
+
+    fn assign_here(&mut self, name: &Token, value: Literal) -> LoxResult<()> {
+        if self.constants.contains(&*name.lexeme) {
+            return Err(Error::RunTime(RuntimeError {
+                token: name.clone(),
+                message: format!("Can't assign to constant '{}'.", name.lexeme),
+            }));
+        }
+        self.values.insert(name.lexeme.to_string(), value);
+        Ok(())
+    }
+}
+
+pub(crate) fn undefined_variable(name: &Token) -> Error {
+    Error::RunTime(RuntimeError {
+        token: name.clone(),
+        message: format!("Undefined variable '{}'.", name.lexeme),
+    })
 }
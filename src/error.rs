@@ -1,11 +1,32 @@
-use crate::token::{Token, TokenKind};
+use crate::token::{Literal, Token, TokenKind};
 use std::fmt;
 
 #[derive(Debug, Clone)]
-pub enum Error<'a> {
+pub enum Error {
     Lexical(LexerError),
-    Syntactic(ParserError<'a>),
-    RunTime(String),
+    // Every syntax error found in the source, in order
+    Syntactic(Vec<ParserError>),
+    // Reported by the resolver for code that parses but can never be valid
+    Semantic(ParserError),
+    RunTime(RuntimeError),
+    // A value thrown by a `throw` statement that no `catch` handled yet
+    Throw {
+        value: Literal,
+        keyword: Token,
+    },
+    // Any of the above, raised by the file imported as `path` while it was
+    // being loaded
+    Import {
+        path: String,
+        keyword: Token,
+        error: Box<Error>,
+    },
+    // Not an actual error, it unwinds the interpreter from a `return`
+    // statement back to the call of the function it is in.
+    Return(Literal),
+    // Like `Return`, they unwind out of the innermost loop
+    Break,
+    Continue,
 }
 
 #[derive(Debug, Clone)]
@@ -15,33 +36,87 @@ pub struct LexerError {
 }
 
 #[derive(Debug, Clone)]
-pub struct ParserError<'a> {
-    pub(crate) token: Token<'a>,
+pub struct ParserError {
+    pub(crate) token: Token,
     pub(crate) message: String,
 }
 
-impl<'a> fmt::Display for Error<'a> {
+#[derive(Debug, Clone)]
+pub struct RuntimeError {
+    pub(crate) token: Token,
+    pub(crate) message: String,
+}
+
+impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Self::Lexical(l) => write!(f, "[line: {}] Lexical error: {}", l.line, l.message),
-            Self::Syntactic(p) => {
-                if p.token.kind == TokenKind::Eof {
-                    write!(
-                        f,
-                        "[line: {}] Syntactic error: {} at end",
-                        p.token.line, p.message
-                    )
-                } else {
-                    write!(
-                        f,
-                        "[line: {}] Syntactic error: {} at '{}'",
-                        p.token.line, p.message, p.token.lexeme
-                    )
+            Self::Lexical(l) => write!(f, "[line {}] Lexical error: {}", l.line, l.message),
+            Self::Syntactic(errors) => {
+                for (i, p) in errors.iter().enumerate() {
+                    if i > 0 {
+                        writeln!(f)?;
+                    }
+                    write!(f, "[line {}] Syntactic error: {}", p.token.line, p)?;
                 }
+                Ok(())
+            }
+            Self::Semantic(p) => write!(f, "[line {}] Semantic error: {}", p.token.line, p),
+            Self::RunTime(r) => write!(f, "[line {}] Runtime error: {}", r.token.line, r.message),
+            Self::Throw { value, keyword } => {
+                write!(f, "[line {}] Uncaught exception: {}", keyword.line, value)
             }
-            Self::RunTime(s) => write!(f, "{}", s),
+            Self::Import {
+                path,
+                keyword,
+                error,
+            } => write!(
+                f,
+                "{}\n    in module '{}' imported on line {}",
+                error, path, keyword.line
+            ),
+            Self::Return(_) => write!(f, "Can't return from top-level code."),
+            Self::Break => write!(f, "Can't use 'break' outside of a loop."),
+            Self::Continue => write!(f, "Can't use 'continue' outside of a loop."),
+        }
+    }
+}
+
+impl fmt::Display for ParserError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.token.kind == TokenKind::Eof {
+            write!(f, "{} at end", self.message)
+        } else {
+            write!(f, "{} at '{}'", self.message, self.token.lexeme)
         }
     }
 }
 
-pub type LoxResult<'a, T> = Result<T, Error<'a>>;
+impl Error {
+    // The error itself, or the one an import failed with
+    pub fn cause(&self) -> &Error {
+        match self {
+            Self::Import { error, .. } => error.cause(),
+            _ => self,
+        }
+    }
+}
+
+pub type LoxResult<T> = Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use crate::error::{Error, RuntimeError};
+    use crate::token::{Token, TokenKind};
+
+    #[test]
+    fn runtime_error_reports_line() {
+        let error = Error::RunTime(RuntimeError {
+            token: Token::new(TokenKind::Identifier, "b", 18, 3),
+            message: "Undefined variable 'b'.".to_string(),
+        });
+        assert_eq!(
+            "[line 3] Runtime error: Undefined variable 'b'.",
+            error.to_string()
+        );
+    }
+}
@@ -1,45 +1,95 @@
+use crate::stmt::FunctionDecl;
 use crate::token::{Literal, Token};
+use std::rc::Rc;
+
+// Identifies a variable, `this` or `super` expression, so that the
+// interpreter can look up how far away the resolver found it bound.
+pub type ExprId = usize;
 
 #[derive(Clone, PartialEq, Debug)]
-pub enum Expr<'a> {
+pub enum Expr {
     Assign {
-        name: Token<'a>,
-        value: Box<Expr<'a>>,
+        id: ExprId,
+        name: Token,
+        value: Box<Expr>,
     },
     Binary {
-        left: Box<Expr<'a>>,
-        operator: Token<'a>,
-        right: Box<Expr<'a>>,
+        left: Box<Expr>,
+        operator: Token,
+        right: Box<Expr>,
     },
     Call {
-        callee: Box<Expr<'a>>,
-        paren: Token<'a>,
-        arguments: Vec<Box<Expr<'a>>>,
+        callee: Box<Expr>,
+        paren: Token,
+        arguments: Vec<Box<Expr>>,
+    },
+    // `target op= value`, where target is a variable, field or index
+    // expression and operator is the binary operator to apply
+    CompoundAssign {
+        target: Box<Expr>,
+        operator: Token,
+        value: Box<Expr>,
+    },
+    Conditional {
+        condition: Box<Expr>,
+        then_branch: Box<Expr>,
+        else_branch: Box<Expr>,
     },
     Get {
-        object: Box<Expr<'a>>,
-        name: Token<'a>,
+        object: Box<Expr>,
+        name: Token,
     },
-    Grouping(Box<Expr<'a>>),
+    Grouping(Box<Expr>),
+    Index {
+        object: Box<Expr>,
+        bracket: Token,
+        index: Box<Expr>,
+    },
+    // The parts of an interpolated string, in order
+    Interpolation(Vec<Box<Expr>>),
+    Lambda(Rc<FunctionDecl>),
+    List(Vec<Box<Expr>>),
     Literal(Literal),
+    Map {
+        brace: Token,
+        entries: Vec<(Box<Expr>, Box<Expr>)>,
+    },
     Logical {
-        left: Box<Expr<'a>>,
-        operator: Token<'a>,
-        right: Box<Expr<'a>>,
+        left: Box<Expr>,
+        operator: Token,
+        right: Box<Expr>,
+    },
+    // `target++` or `target--`, evaluates to the value before the update
+    Postfix {
+        target: Box<Expr>,
+        operator: Token,
     },
     Set {
-        object: Box<Expr<'a>>,
-        name: Token<'a>,
-        value: Box<Expr<'a>>,
+        object: Box<Expr>,
+        name: Token,
+        value: Box<Expr>,
+    },
+    SetIndex {
+        object: Box<Expr>,
+        bracket: Token,
+        index: Box<Expr>,
+        value: Box<Expr>,
     },
     Super {
-        keyword: Token<'a>,
-        method: Token<'a>,
+        id: ExprId,
+        keyword: Token,
+        method: Token,
+    },
+    This {
+        id: ExprId,
+        keyword: Token,
     },
-    This(Token<'a>),
     Unary {
-        operator: Token<'a>,
-        right: Box<Expr<'a>>,
+        operator: Token,
+        right: Box<Expr>,
+    },
+    Variable {
+        id: ExprId,
+        name: Token,
     },
-    Variable(Token<'a>),
 }
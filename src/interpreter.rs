@@ -1,27 +1,64 @@
-use crate::environment::Environment;
-use crate::error::{Error, LoxResult};
-use crate::expr::Expr;
+use crate::environment::{undefined_variable, Environment};
+use crate::error::{Error, LoxResult, RuntimeError};
+use crate::expr::{Expr, ExprId};
+use crate::lexer;
+use crate::lox;
 use crate::lox_callable::LoxCallable;
+use crate::lox_class::LoxClass;
+use crate::lox_function::LoxFunction;
+use crate::lox_instance::LoxInstance;
+use crate::lox_module::LoxModule;
+use crate::natives;
 use crate::stmt::Stmt;
-use crate::stmt::Stmt::Print;
-use crate::token::{Literal, TokenKind};
+use crate::token::{Literal, MapKey, Token, TokenKind};
 use crate::visitor::Visitor;
-use std::cell::{Ref, RefCell};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
-use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use unicode_segmentation::UnicodeSegmentation;
+
+// Deeper recursion is reported as a runtime error instead of using up
+// memory without end.
+const MAX_CALL_DEPTH: usize = 1000;
+
+// Calls and blocks run on a newly allocated stack segment when less than
+// `STACK_RED_ZONE` bytes are left of the current one, so nesting is bounded
+// by `MAX_CALL_DEPTH` and not by the stack of the thread running the script.
+const STACK_RED_ZONE: usize = 1024 * 1024;
+const STACK_SEGMENT: usize = 16 * 1024 * 1024;
 
-// TODO: swap stdout for generic writer, good for tests, maybe also for other reasons?
 pub struct Interpreter {
-    globals: Rc<RefCell<Environment>>,
+    // Where `print` writes to, stdout unless given another writer
+    output: Box<dyn Write>,
     environment: Rc<RefCell<Environment>>,
+    // Scope depth of every resolved local variable, keyed by the id of the
+    // expression that refers to it. Variables missing from it are globals.
+    locals: HashMap<ExprId, usize>,
+    // The class of the values runtime errors are caught as
+    error_class: Rc<LoxClass>,
+    // How many calls are currently running, see `MAX_CALL_DEPTH`
+    call_depth: usize,
+    // Every module imported so far, by canonical path
+    modules: HashMap<PathBuf, Rc<LoxModule>>,
+    // The files being run, the innermost import last
+    files: Vec<PathBuf>,
 }
 
 // Need to change literal to token, for error handling???
-impl<'a> Visitor<LoxResult<'a, Literal>, LoxResult<'a, ()>> for Interpreter {
-    fn visit_expr(&mut self, e: &Expr) -> LoxResult<'a, Literal> {
+impl Visitor<LoxResult<Literal>, LoxResult<()>> for Interpreter {
+    fn visit_expr(&mut self, e: &Expr) -> LoxResult<Literal> {
         match e {
             Expr::Literal(literal) => Ok(literal.clone()),
             Expr::Grouping(ref e) => self.visit_expr(e),
+            Expr::Lambda(declaration) => Ok(Literal::Callable(Rc::new(LoxFunction::new(
+                Rc::clone(declaration),
+                Rc::clone(&self.environment),
+                false,
+            )))),
             Expr::Unary {
                 ref right,
                 operator,
@@ -31,7 +68,10 @@ impl<'a> Visitor<LoxResult<'a, Literal>, LoxResult<'a, ()>> for Interpreter {
                 match (right, operator.kind) {
                     (Literal::Number(number), TokenKind::Minus) => Ok(Literal::Number(-number)),
                     (r, TokenKind::Bang) => Ok(Literal::Bool(!is_truthy(&r))),
-                    _ => unreachable!(),
+                    (_, TokenKind::Minus) => {
+                        Err(runtime_error(operator, "Operand must be a number."))
+                    }
+                    _ => Err(unsupported_operator(operator)),
                 }
             }
             Expr::Binary {
@@ -41,52 +81,23 @@ impl<'a> Visitor<LoxResult<'a, Literal>, LoxResult<'a, ()>> for Interpreter {
             } => {
                 let left = self.visit_expr(left)?;
                 let right = self.visit_expr(right)?;
-
-                // Maybe change to two levels of match operation.kind => literal
-                match (left, right, operator.kind) {
-                    (Literal::Number(n1), Literal::Number(n2), TokenKind::Minus) => {
-                        Ok(Literal::Number(n1 - n2))
-                    }
-                    (Literal::Number(n1), Literal::Number(n2), TokenKind::Plus) => {
-                        Ok(Literal::Number(n1 + n2))
-                    }
-                    (Literal::Number(n1), Literal::Number(n2), TokenKind::Slash) => {
-                        Ok(Literal::Number(n1 / n2))
-                    }
-                    (Literal::Number(n1), Literal::Number(n2), TokenKind::Star) => {
-                        Ok(Literal::Number(n1 * n2))
-                    }
-                    (Literal::String(s1), Literal::String(s2), TokenKind::Plus) => {
-                        Ok(Literal::String(s1 + &s2))
-                    }
-                    (Literal::Number(n1), Literal::Number(n2), TokenKind::Greater) => {
-                        Ok(Literal::Bool(n1 > n2))
-                    }
-                    (Literal::Number(n1), Literal::Number(n2), TokenKind::GreaterEqual) => {
-                        Ok(Literal::Bool(n1 >= n2))
-                    }
-                    (Literal::Number(n1), Literal::Number(n2), TokenKind::Less) => {
-                        Ok(Literal::Bool(n1 < n2))
-                    }
-                    (Literal::Number(n1), Literal::Number(n2), TokenKind::LessEqual) => {
-                        Ok(Literal::Bool(n1 <= n2))
-                    }
-                    (r, l, TokenKind::Equal) => Ok(Literal::Bool(r == l)),
-                    (l, r, TokenKind::Plus) => Err(Error::RunTime(format!(
-                        "({} + {}), both should be a number",
-                        l, r
-                    ))),
-                    _ => unreachable!(),
-                }
+                binary(operator, left, right)
             }
-            Expr::Variable(token) => self.environment.borrow().get(token),
-            Expr::Assign { name, value } => {
+            Expr::Variable { id, name } => self.look_up_variable(*id, name),
+            Expr::Assign { id, name, value } => {
                 let value = self.visit_expr(value)?;
-                self.environment
-                    .borrow_mut()
-                    .assign(name.lexeme.to_string(), value.clone())?;
+                self.assign_variable(*id, name, value.clone())?;
                 Ok(value)
             }
+            Expr::CompoundAssign {
+                target,
+                operator,
+                value,
+            } => Ok(self.update(target, operator, value)?.1),
+            Expr::Postfix { target, operator } => {
+                let one = Expr::Literal(Literal::Number(1.0));
+                Ok(self.update(target, operator, &one)?.0)
+            }
             Expr::Logical {
                 left,
                 operator,
@@ -114,49 +125,190 @@ impl<'a> Visitor<LoxResult<'a, Literal>, LoxResult<'a, ()>> for Interpreter {
                     args.push(self.visit_expr(arg)?);
                 }
 
-                match callee {
-                    Literal::Callable(c) => {
-                        let function = c;
-                        if arguments.len() != function.arity() {
-                            return Err(Error::RunTime(format!(
-                                "Expected {} arguments but got {}.",
-                                function.arity(),
-                                arguments.len()
-                            )));
-                        }
-                        Ok(function.call(&mut self, arguments))
+                let function: Rc<dyn LoxCallable> = match callee {
+                    Literal::Callable(function) => function,
+                    Literal::Class(class) => class,
+                    _ => return Err(runtime_error(paren, "Can only call functions and classes.")),
+                };
+                let arity = function.arity();
+                if !arity.accepts(args.len()) {
+                    return Err(runtime_error(
+                        paren,
+                        &format!("Expected {} arguments but got {}.", arity, args.len()),
+                    ));
+                }
+                if self.call_depth == MAX_CALL_DEPTH {
+                    return Err(runtime_error(paren, "Stack overflow."));
+                }
+                self.call_depth += 1;
+                let result = stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT, || {
+                    function.call(self, paren, args)
+                });
+                self.call_depth -= 1;
+                result
+            }
+            Expr::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                if is_truthy(&self.visit_expr(condition)?) {
+                    self.visit_expr(then_branch)
+                } else {
+                    self.visit_expr(else_branch)
+                }
+            }
+            Expr::Get { object, name } => match self.visit_expr(object)? {
+                Literal::Instance(instance) => LoxInstance::get(&instance, name),
+                Literal::Module(module) => module.get(name),
+                _ => Err(runtime_error(
+                    name,
+                    "Only instances and modules have properties.",
+                )),
+            },
+            Expr::Set {
+                object,
+                name,
+                value,
+            } => {
+                let Literal::Instance(instance) = self.visit_expr(object)? else {
+                    return Err(runtime_error(name, "Only instances have fields."));
+                };
+                let value = self.visit_expr(value)?;
+                instance.borrow_mut().set(&name.lexeme, value.clone());
+                Ok(value)
+            }
+            Expr::Index {
+                object,
+                bracket,
+                index,
+            } => {
+                let object = self.visit_expr(object)?;
+                let index = self.visit_expr(index)?;
+                match object {
+                    Literal::List(list) => {
+                        let list = list.borrow();
+                        let index = natives::list_index(bracket, &index, list.len())?;
+                        Ok(list[index].clone())
+                    }
+                    Literal::Map(map) => {
+                        let key = natives::map_key(bracket, &index)?;
+                        map.borrow().get(&key).cloned().ok_or_else(|| {
+                            runtime_error(bracket, &format!("Undefined key '{}'.", key))
+                        })
+                    }
+                    _ => Err(runtime_error(
+                        bracket,
+                        "Only lists and maps can be indexed.",
+                    )),
+                }
+            }
+            Expr::SetIndex {
+                object,
+                bracket,
+                index,
+                value,
+            } => {
+                let object = self.visit_expr(object)?;
+                let index = self.visit_expr(index)?;
+                let value = self.visit_expr(value)?;
+                match object {
+                    Literal::List(list) => {
+                        let mut list = list.borrow_mut();
+                        let index = natives::list_index(bracket, &index, list.len())?;
+                        list[index] = value.clone();
+                    }
+                    Literal::Map(map) => {
+                        let key = natives::map_key(bracket, &index)?;
+                        map.borrow_mut().insert(key, value.clone());
                     }
                     _ => {
-                        return Err(Error::RunTime(
-                            "Can only call function and classes".to_string(),
-                        ));
+                        return Err(runtime_error(
+                            bracket,
+                            "Only lists and maps can be indexed.",
+                        ))
                     }
                 }
+                Ok(value)
+            }
+            Expr::Interpolation(parts) => {
+                let mut string = String::new();
+                for part in parts {
+                    string += &self.visit_expr(part)?.to_string();
+                }
+                Ok(Literal::String(string))
+            }
+            Expr::List(elements) => {
+                let elements = elements
+                    .iter()
+                    .map(|element| self.visit_expr(element))
+                    .collect::<LoxResult<Vec<_>>>()?;
+                Ok(Literal::List(Rc::new(RefCell::new(elements))))
+            }
+            Expr::Map { brace, entries } => {
+                let mut map = BTreeMap::new();
+                for (key, value) in entries {
+                    let key = self.visit_expr(key)?;
+                    let key = natives::map_key(brace, &key)?;
+                    map.insert(key, self.visit_expr(value)?);
+                }
+                Ok(Literal::Map(Rc::new(RefCell::new(map))))
+            }
+            Expr::This { id, keyword } => self.look_up_variable(*id, keyword),
+            Expr::Super {
+                id,
+                keyword,
+                method,
+            } => {
+                // The resolver only lets `super` through inside subclass
+                // methods, where `this` is bound one environment below it
+                let Some(&distance) = self.locals.get(id) else {
+                    return Err(runtime_error(
+                        keyword,
+                        "Can't use 'super' outside of a class.",
+                    ));
+                };
+                let environment = self.environment.borrow();
+                let Some(Literal::Class(superclass)) = environment.get_at(distance, "super") else {
+                    return Err(runtime_error(keyword, "Superclass must be a class."));
+                };
+                let Some(object) = environment.get_at(distance - 1, "this") else {
+                    return Err(runtime_error(
+                        keyword,
+                        "Can't use 'super' outside of a class.",
+                    ));
+                };
+
+                match superclass.find_method(&method.lexeme) {
+                    Some(found) => Ok(Literal::Callable(Rc::new(found.bind(object)))),
+                    None => Err(runtime_error(
+                        method,
+                        &format!("Undefined property '{}'.", method.lexeme),
+                    )),
+                }
             }
-            _ => todo!(),
         }
     }
 
-    fn visit_stmt(&mut self, stmt: &Stmt) -> LoxResult<'a, ()> {
+    fn visit_stmt(&mut self, stmt: &Stmt) -> LoxResult<()> {
         match stmt {
             Stmt::Expression(expr) => {
-                self.visit_expr(expr).unwrap();
+                self.visit_expr(expr)?;
             }
-            Stmt::Print(expr) => {
-                let value = self.visit_expr(expr).unwrap();
-                println!("{}", value);
+            Stmt::Print { keyword, value } => {
+                let value = self.visit_expr(value)?;
+                writeln!(self.output, "{}", value)
+                    .map_err(|error| runtime_error(keyword, &format!("Can't print: {}.", error)))?;
             }
             Stmt::Var(name, initializer) => {
                 let value = self.visit_expr(initializer)?;
-                self.environment
-                    .borrow_mut()
-                    .define(name.lexeme.to_string(), value);
+                self.environment.borrow_mut().define(name, value)?;
             }
-            Stmt::Block(stmts) => {
-                let environment =
-                    Rc::new(RefCell::new(Environment::new(Rc::clone(&self.environment))));
-                self.execute_block(stmts, environment);
+            Stmt::Const(name, initializer) => {
+                let value = self.visit_expr(initializer)?;
+                self.environment.borrow_mut().define_constant(name, value)?;
             }
+            Stmt::Block(stmts) => self.execute_block(stmts, self.enclosed_environment())?,
             Stmt::If {
                 condition,
                 then_branch,
@@ -169,16 +321,284 @@ impl<'a> Visitor<LoxResult<'a, Literal>, LoxResult<'a, ()>> for Interpreter {
                     self.visit_stmt(else_stmt)?;
                 }
             }
-            Stmt::While { condition, body } => {
+            Stmt::While {
+                condition,
+                body,
+                increment,
+            } => {
                 while is_truthy(&self.visit_expr(condition)?) {
-                    self.visit_stmt(body)?;
+                    match self.visit_stmt(body) {
+                        Ok(()) | Err(Error::Continue) => (),
+                        Err(Error::Break) => break,
+                        Err(err) => return Err(err),
+                    }
+                    if let Some(increment) = increment {
+                        self.visit_expr(increment)?;
+                    }
+                }
+            }
+            Stmt::ForIn {
+                name,
+                keyword,
+                iterable,
+                body,
+            } => {
+                // Lists and maps are copied first, so the body is free to change them
+                let values: Box<dyn Iterator<Item = Literal>> = match self.visit_expr(iterable)? {
+                    Literal::List(list) => Box::new(list.borrow().clone().into_iter()),
+                    Literal::Map(map) => Box::new(
+                        map.borrow()
+                            .keys()
+                            .map(MapKey::to_literal)
+                            .collect::<Vec<_>>()
+                            .into_iter(),
+                    ),
+                    Literal::String(s) => Box::new(
+                        s.graphemes(true)
+                            .map(|grapheme| Literal::String(grapheme.to_string()))
+                            .collect::<Vec<_>>()
+                            .into_iter(),
+                    ),
+                    Literal::Range(range) => Box::new(range.iter().map(Literal::Number)),
+                    _ => {
+                        return Err(runtime_error(
+                            keyword,
+                            "Can only iterate over lists, maps, strings and ranges.",
+                        ))
+                    }
+                };
+                for value in values {
+                    let environment = self.enclosed_environment();
+                    environment.borrow_mut().define(name, value)?;
+                    match self.execute_block(std::slice::from_ref(body), environment) {
+                        Ok(()) | Err(Error::Continue) => (),
+                        Err(Error::Break) => break,
+                        Err(err) => return Err(err),
+                    }
+                }
+            }
+            Stmt::Import {
+                keyword,
+                path,
+                alias,
+            } => {
+                let Some(Literal::String(path)) = &path.literal else {
+                    return Err(runtime_error(path, "Module path must be a string."));
+                };
+                let name = match alias {
+                    Some(alias) => alias.clone(),
+                    None => module_name(keyword, Path::new(path))?,
+                };
+                let module = self.import(keyword, path)?;
+                self.environment
+                    .borrow_mut()
+                    .define(&name, Literal::Module(module))?;
+            }
+            Stmt::Throw { keyword, value } => {
+                return Err(Error::Throw {
+                    value: self.visit_expr(value)?,
+                    keyword: keyword.clone(),
+                })
+            }
+            Stmt::Try {
+                body,
+                catch,
+                finally,
+            } => {
+                let mut result = self.execute_block(body, self.enclosed_environment());
+                if let Some((name, handler)) = catch {
+                    let caught = match &result {
+                        Err(Error::Throw { value, .. }) => Some(value.clone()),
+                        Err(Error::RunTime(error)) => Some(self.error_value(error)),
+                        _ => None,
+                    };
+                    if let Some(caught) = caught {
+                        let environment = self.enclosed_environment();
+                        environment.borrow_mut().define(name, caught)?;
+                        result = self.execute_block(handler, environment);
+                    }
+                }
+                // `finally` runs however the rest ended, `return`, `break`
+                // and uncaught errors included, and only takes over if it
+                // doesn't complete normally itself
+                if let Some(finally) = finally {
+                    self.execute_block(finally, self.enclosed_environment())?;
+                }
+                result?;
+            }
+            Stmt::Break(_) => return Err(Error::Break),
+            Stmt::Continue(_) => return Err(Error::Continue),
+            Stmt::Function(declaration) => {
+                let function =
+                    LoxFunction::new(Rc::clone(declaration), Rc::clone(&self.environment), false);
+                self.environment
+                    .borrow_mut()
+                    .define(&declaration.name, Literal::Callable(Rc::new(function)))?;
+            }
+            Stmt::Return { value, .. } => {
+                let value = match value {
+                    Some(value) => self.visit_expr(value)?,
+                    None => Literal::Nil,
+                };
+                return Err(Error::Return(value));
+            }
+            Stmt::Class {
+                name,
+                superclass,
+                methods,
+            } => {
+                let superclass = match superclass {
+                    Some(superclass) => match self.visit_expr(superclass)? {
+                        Literal::Class(class) => Some(class),
+                        _ => return Err(runtime_error(name, "Superclass must be a class.")),
+                    },
+                    None => None,
+                };
+
+                // Methods of a subclass see `super` one environment above `this`
+                let mut closure = Rc::clone(&self.environment);
+                if let Some(superclass) = &superclass {
+                    let mut environment = Environment::new(closure);
+                    environment.define_implicit(
+                        "super".to_string(),
+                        Literal::Class(Rc::clone(superclass)),
+                    );
+                    closure = Rc::new(RefCell::new(environment));
+                }
+
+                let mut class_methods = HashMap::new();
+                for method in methods {
+                    let function = LoxFunction::new(
+                        Rc::clone(method),
+                        Rc::clone(&closure),
+                        &*method.name.lexeme == "init",
+                    );
+                    class_methods.insert(method.name.lexeme.to_string(), Rc::new(function));
                 }
+
+                let class = LoxClass::new(name.lexeme.to_string(), superclass, class_methods);
+                self.environment
+                    .borrow_mut()
+                    .define(name, Literal::Class(Rc::new(class)))?;
             }
         }
         Ok(())
     }
 }
 
+fn binary(operator: &Token, left: Literal, right: Literal) -> LoxResult<Literal> {
+    // Maybe change to two levels of match operation.kind => literal
+    match (left, right, operator.kind) {
+        (Literal::Number(n1), Literal::Number(n2), TokenKind::Minus) => {
+            Ok(Literal::Number(n1 - n2))
+        }
+        (Literal::Number(n1), Literal::Number(n2), TokenKind::Plus) => Ok(Literal::Number(n1 + n2)),
+        (Literal::Number(n1), Literal::Number(n2), TokenKind::Slash) => {
+            Ok(Literal::Number(n1 / n2))
+        }
+        (Literal::Number(n1), Literal::Number(n2), TokenKind::Star) => Ok(Literal::Number(n1 * n2)),
+        // `%` and `~/` both round the quotient down, so the remainder takes
+        // the sign of the divisor and `a == b * (a ~/ b) + a % b` holds
+        (Literal::Number(n1), Literal::Number(n2), TokenKind::Percent) => {
+            Ok(Literal::Number(n1 - n2 * (n1 / n2).floor()))
+        }
+        (Literal::Number(n1), Literal::Number(n2), TokenKind::TildeSlash) => {
+            Ok(Literal::Number((n1 / n2).floor()))
+        }
+        (Literal::Number(n1), Literal::Number(n2), TokenKind::StarStar) => {
+            Ok(Literal::Number(n1.powf(n2)))
+        }
+        (Literal::String(s1), Literal::String(s2), TokenKind::Plus) => {
+            Ok(Literal::String(s1 + &s2))
+        }
+        (Literal::Number(n1), Literal::Number(n2), TokenKind::Greater) => {
+            Ok(Literal::Bool(n1 > n2))
+        }
+        (Literal::Number(n1), Literal::Number(n2), TokenKind::GreaterEqual) => {
+            Ok(Literal::Bool(n1 >= n2))
+        }
+        (Literal::Number(n1), Literal::Number(n2), TokenKind::Less) => Ok(Literal::Bool(n1 < n2)),
+        (Literal::Number(n1), Literal::Number(n2), TokenKind::LessEqual) => {
+            Ok(Literal::Bool(n1 <= n2))
+        }
+        (l, r, TokenKind::EqualEqual) => Ok(Literal::Bool(l == r)),
+        (l, r, TokenKind::BangEqual) => Ok(Literal::Bool(l != r)),
+        (_, _, TokenKind::Plus) => Err(runtime_error(
+            operator,
+            "Operands must be two numbers or two strings.",
+        )),
+        (
+            _,
+            _,
+            TokenKind::Minus
+            | TokenKind::Slash
+            | TokenKind::Star
+            | TokenKind::Percent
+            | TokenKind::TildeSlash
+            | TokenKind::StarStar
+            | TokenKind::Greater
+            | TokenKind::GreaterEqual
+            | TokenKind::Less
+            | TokenKind::LessEqual,
+        ) => Err(runtime_error(operator, "Operands must be numbers.")),
+        _ => Err(unsupported_operator(operator)),
+    }
+}
+
+// A fresh environment for the globals of a script or module, enclosed by
+// one holding the builtins so that a module only exposes its own globals
+fn global_environment(error_class: &Rc<LoxClass>) -> Rc<RefCell<Environment>> {
+    let mut builtins = Environment::new_global();
+    natives::define(&mut builtins);
+    builtins.define_implicit("Error".to_string(), Literal::Class(Rc::clone(error_class)));
+    let globals = Environment::new(Rc::new(RefCell::new(builtins)));
+    Rc::new(RefCell::new(globals))
+}
+
+fn cant_import(keyword: &Token, path: &Path, error: io::Error) -> Error {
+    runtime_error(
+        keyword,
+        &format!("Can't import '{}': {}.", path.display(), error),
+    )
+}
+
+// The name a module imported without `as` is bound to, as if it was
+// written where the import is
+fn module_name(keyword: &Token, path: &Path) -> LoxResult<Token> {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .filter(|stem| is_identifier(stem))
+        .map(|stem| Token::new(TokenKind::Identifier, stem, keyword.start, keyword.line))
+        .ok_or_else(|| {
+            runtime_error(
+                keyword,
+                &format!(
+                    "Module file name of '{}' isn't a valid identifier, name it with 'as'.",
+                    path.display()
+                ),
+            )
+        })
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(lexer::is_alpha) && chars.all(lexer::is_alphanumeric)
+}
+
+pub(crate) fn runtime_error(token: &Token, message: &str) -> Error {
+    Error::RunTime(RuntimeError {
+        token: token.clone(),
+        message: message.to_string(),
+    })
+}
+
+fn unsupported_operator(operator: &Token) -> Error {
+    runtime_error(
+        operator,
+        &format!("Unsupported operator '{}'.", operator.lexeme),
+    )
+}
+
 fn is_truthy(literal: &Literal) -> bool {
     match literal {
         Literal::Nil => false,
@@ -187,69 +607,234 @@ fn is_truthy(literal: &Literal) -> bool {
     }
 }
 
-#[derive(Clone, PartialEq, Debug)]
-struct LoxFunction<'a> {
-    declaration: Stmt<'a>,
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-impl LoxCallable for LoxFunction {
-    fn arity(&self) -> usize {
-        todo!()
+impl Interpreter {
+    pub fn new() -> Self {
+        Self::with_output(Box::new(io::stdout()))
     }
 
-    fn call(&self, interpreter: &mut Interpreter, arguments: &[Box<Expr>]) -> Literal {
-        let environment = Rc::clone(&interpreter.globals);
-        for
-        todo!()
+    pub fn with_output(output: Box<dyn Write>) -> Self {
+        let error_class = Rc::new(LoxClass::new("Error".to_string(), None, HashMap::new()));
+        Self {
+            output,
+            environment: global_environment(&error_class),
+            locals: HashMap::new(),
+            call_depth: 0,
+            error_class,
+            modules: HashMap::new(),
+            files: Vec::new(),
+        }
     }
-}
 
-#[derive(Clone, PartialEq, Debug)]
-struct Clock;
+    pub(crate) fn enter_file(&mut self, path: &Path) {
+        let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        self.files.push(path);
+    }
 
-impl LoxCallable for Clock {
-    fn arity(&self) -> usize {
-        0
+    pub(crate) fn leave_file(&mut self) {
+        self.files.pop();
     }
 
-    fn call(&self, _interpreter: &mut Interpreter, _arguments: &[Box<Expr>]) -> Literal {
-        let time = match SystemTime::now().duration_since(UNIX_EPOCH) {
-            Ok(n) => n.as_millis(),
-            Err(_) => panic!("SystemTime before UNIX EPOCH!"),
+    // Runs the file at `path` the first time it is imported, in its own
+    // global environment. Later imports get the same module back.
+    fn import(&mut self, keyword: &Token, path: &str) -> LoxResult<Rc<LoxModule>> {
+        let written_path = path;
+        let directory = self
+            .files
+            .last()
+            .and_then(|file| file.parent())
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        let path = directory.join(path);
+        let path = fs::canonicalize(&path).map_err(|error| cant_import(keyword, &path, error))?;
+        if let Some(module) = self.modules.get(&path) {
+            return Ok(Rc::clone(module));
+        }
+        if let Some(start) = self.files.iter().position(|file| *file == path) {
+            let cycle = self.files[start..]
+                .iter()
+                .chain([&path])
+                .map(|file| file.file_name().unwrap_or_default().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            return Err(runtime_error(keyword, &format!("Import cycle: {}.", cycle)));
+        }
+
+        let name = path
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        let source =
+            fs::read_to_string(&path).map_err(|error| cant_import(keyword, &path, error))?;
+
+        // Errors in the module's own code say which module they come from
+        let in_module = |error| Error::Import {
+            path: written_path.to_string(),
+            keyword: keyword.clone(),
+            error: Box::new(error),
         };
-        Literal::Number((time / 1000) as f64)
+        let statements = lox::compile(&source, self).map_err(in_module)?;
+        let globals = global_environment(&self.error_class);
+        self.enter_file(&path);
+        let result = self.execute_block(&statements, Rc::clone(&globals));
+        self.leave_file();
+        result.map_err(in_module)?;
+
+        let module = Rc::new(LoxModule::new(name, globals));
+        self.modules.insert(path, Rc::clone(&module));
+        Ok(module)
     }
-}
 
-impl Interpreter {
-    pub fn new() -> Self {
-        let globals = Rc::new(RefCell::new(Environment::new_global()));
-        let environment = Rc::clone(&globals);
-        globals
-            .borrow_mut()
-            .define("clock".to_string(), Literal::Callable(Box::new(Clock)));
-        Self {
-            globals,
-            environment,
+    pub(crate) fn resolve(&mut self, id: ExprId, depth: usize) {
+        self.locals.insert(id, depth);
+    }
+
+    fn look_up_variable(&self, id: ExprId, name: &Token) -> LoxResult<Literal> {
+        match self.locals.get(&id) {
+            Some(distance) => self
+                .environment
+                .borrow()
+                .get_at(*distance, &name.lexeme)
+                .ok_or_else(|| undefined_variable(name)),
+            None => Environment::globals(&self.environment).borrow().get(name),
+        }
+    }
+
+    fn assign_variable(&mut self, id: ExprId, name: &Token, value: Literal) -> LoxResult<()> {
+        match self.locals.get(&id) {
+            Some(distance) => self
+                .environment
+                .borrow_mut()
+                .assign_at(*distance, name, value),
+            None => Environment::globals(&self.environment)
+                .borrow_mut()
+                .assign(name, value),
+        }
+    }
+
+    // Applies `operator` to the current value of `target` and `value` and
+    // stores the result, returning both the old and the new value. The parts
+    // of the target (object, index) are evaluated once, and its current value
+    // is read before `value` is evaluated, as in `target = target op value`.
+    fn update(
+        &mut self,
+        target: &Expr,
+        operator: &Token,
+        value: &Expr,
+    ) -> LoxResult<(Literal, Literal)> {
+        match target {
+            Expr::Variable { id, name } => {
+                let old = self.look_up_variable(*id, name)?;
+                let value = self.visit_expr(value)?;
+                let new = binary(operator, old.clone(), value)?;
+                self.assign_variable(*id, name, new.clone())?;
+                Ok((old, new))
+            }
+            Expr::Get { object, name } => {
+                let Literal::Instance(instance) = self.visit_expr(object)? else {
+                    return Err(runtime_error(name, "Only instances have fields."));
+                };
+                let old = LoxInstance::get(&instance, name)?;
+                let value = self.visit_expr(value)?;
+                let new = binary(operator, old.clone(), value)?;
+                instance.borrow_mut().set(&name.lexeme, new.clone());
+                Ok((old, new))
+            }
+            Expr::Index {
+                object,
+                bracket,
+                index,
+            } => {
+                let object = self.visit_expr(object)?;
+                let index = self.visit_expr(index)?;
+                match object {
+                    Literal::List(list) => {
+                        let position = natives::list_index(bracket, &index, list.borrow().len())?;
+                        let old = list.borrow()[position].clone();
+                        let value = self.visit_expr(value)?;
+                        let new = binary(operator, old.clone(), value)?;
+                        // `value` may have shrunk the list
+                        let position = natives::list_index(bracket, &index, list.borrow().len())?;
+                        list.borrow_mut()[position] = new.clone();
+                        Ok((old, new))
+                    }
+                    Literal::Map(map) => {
+                        let key = natives::map_key(bracket, &index)?;
+                        let old = map.borrow().get(&key).cloned().ok_or_else(|| {
+                            runtime_error(bracket, &format!("Undefined key '{}'.", key))
+                        })?;
+                        let value = self.visit_expr(value)?;
+                        let new = binary(operator, old.clone(), value)?;
+                        map.borrow_mut().insert(key, new.clone());
+                        Ok((old, new))
+                    }
+                    _ => Err(runtime_error(
+                        bracket,
+                        "Only lists and maps can be indexed.",
+                    )),
+                }
+            }
+            // The parser only builds updates of the targets above
+            _ => Err(runtime_error(operator, "Invalid assignment target.")),
         }
     }
 
-    pub fn interpret(&mut self, statements: Vec<Stmt>) {
+    // Stops at the first runtime error and hands it back to the caller
+    pub fn interpret(&mut self, statements: Vec<Stmt>) -> LoxResult<()> {
         for stmt in statements.iter() {
-            self.visit_stmt(stmt).unwrap_or_else(|err| {
-                eprintln!("{}", err);
-            });
+            self.visit_stmt(stmt)?;
         }
+        Ok(())
     }
 
-    fn execute_block(&mut self, statements: &[Stmt], environment: Rc<RefCell<Environment>>) {
+    // Like `execute_block`, for a single expression
+    pub(crate) fn evaluate_in(
+        &mut self,
+        expr: &Expr,
+        environment: Rc<RefCell<Environment>>,
+    ) -> LoxResult<Literal> {
         let mut previous = environment;
         std::mem::swap(&mut self.environment, &mut previous);
+        let result = self.visit_expr(expr);
+        std::mem::swap(&mut self.environment, &mut previous);
+        result
+    }
 
-        for statement in statements.iter() {
-            self.visit_stmt(statement).unwrap();
-        }
+    fn enclosed_environment(&self) -> Rc<RefCell<Environment>> {
+        Rc::new(RefCell::new(Environment::new(Rc::clone(&self.environment))))
+    }
+
+    // Runtime errors reach `catch` as an `Error` instance with
+    // `message` and `line` fields
+    fn error_value(&self, error: &RuntimeError) -> Literal {
+        let mut instance = LoxInstance::new(Rc::clone(&self.error_class));
+        instance.set("message", Literal::String(error.message.clone()));
+        instance.set("line", Literal::Number(error.token.line as f64));
+        Literal::Instance(Rc::new(RefCell::new(instance)))
+    }
+
+    pub(crate) fn execute_block(
+        &mut self,
+        statements: &[Stmt],
+        environment: Rc<RefCell<Environment>>,
+    ) -> LoxResult<()> {
+        let mut previous = environment;
+        std::mem::swap(&mut self.environment, &mut previous);
+
+        // The previous environment has to be restored even when a statement fails
+        let result = stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT, || {
+            statements
+                .iter()
+                .try_for_each(|statement| self.visit_stmt(statement))
+        });
 
         std::mem::swap(&mut self.environment, &mut previous);
+        result
     }
 }
@@ -1,9 +1,7 @@
 use crate::error::{Error, LexerError, LoxResult};
 use crate::token::{Literal, Token, TokenKind};
-use std::hint::unreachable_unchecked;
 use std::iter::{once, Peekable};
 use std::str::Chars;
-// use unicode_segmentation::UnicodeSegmentation;
 
 pub const EOF_CHAR: char = '\0';
 pub const EOF_STR: &str = "\0";
@@ -11,19 +9,28 @@ pub const EOF_STR: &str = "\0";
 fn keywords(identifier: &str) -> Option<TokenKind> {
     match identifier {
         "and" => Some(TokenKind::And),
+        "break" => Some(TokenKind::Break),
+        "catch" => Some(TokenKind::Catch),
         "class" => Some(TokenKind::Class),
+        "const" => Some(TokenKind::Const),
+        "continue" => Some(TokenKind::Continue),
         "else" => Some(TokenKind::Else),
         "false" => Some(TokenKind::False),
+        "finally" => Some(TokenKind::Finally),
         "for" => Some(TokenKind::For),
         "fun" => Some(TokenKind::Fun),
         "if" => Some(TokenKind::If),
+        "import" => Some(TokenKind::Import),
+        "in" => Some(TokenKind::In),
         "nil" => Some(TokenKind::Nil),
         "or" => Some(TokenKind::Or),
         "print" => Some(TokenKind::Print),
         "return" => Some(TokenKind::Return),
         "super" => Some(TokenKind::Super),
         "this" => Some(TokenKind::This),
+        "throw" => Some(TokenKind::Throw),
         "true" => Some(TokenKind::True),
+        "try" => Some(TokenKind::Try),
         "var" => Some(TokenKind::Var),
         "while" => Some(TokenKind::While),
         _ => None,
@@ -50,6 +57,9 @@ pub struct Lexer<'a> {
     start: usize,
     line: usize,
     saw_eof: bool,
+    // One entry per `${` we are inside of, counting the braces opened
+    // since, so we know which `}` goes back to lexing the string
+    interpolations: Vec<usize>,
 }
 
 impl<'a> Lexer<'a> {
@@ -60,8 +70,9 @@ impl<'a> Lexer<'a> {
             length: source.len(),
             cursor: 0,
             start: 0,
-            line: 0,
+            line: 1,
             saw_eof: false,
+            interpolations: Vec::new(),
         }
     }
 
@@ -69,9 +80,11 @@ impl<'a> Lexer<'a> {
         self.chars.clone().next().is_none()
     }
 
+    // The cursor is a byte offset into the source, so it can be used to slice it
     pub fn advance_char(&mut self) -> Option<char> {
-        self.cursor += 1;
-        self.chars.next()
+        let c = self.chars.next();
+        self.cursor += c.map_or(1, char::len_utf8);
+        c
     }
 
     fn peek_first(&mut self) -> char {
@@ -100,31 +113,109 @@ impl<'a> Lexer<'a> {
         }
     }
 
-    fn string(&mut self) -> StringData<'a> {
-        // Len without quotes
-        let mut offset: usize = 1;
-        while self.peek_first() != '"' && !self.is_eof() {
-            if self.peek_first() == '\n' {
-                self.line += 1
+    // The opening " (or the `}` closing an interpolated expression) is
+    // already consumed. Strings may span lines, the token gets the line
+    // the string starts on. A part of the string that ends at `${` is an
+    // Interpolation token, the expression tokens follow it.
+    fn string(&mut self) -> LoxResult<Token> {
+        let line = self.line;
+        let mut value = String::new();
+        let mut kind = TokenKind::String;
+        loop {
+            match self.advance_char() {
+                Some('"') => break,
+                Some('$') if self.advance_if('{') => {
+                    self.interpolations.push(0);
+                    kind = TokenKind::Interpolation;
+                    break;
+                }
+                Some('\\') => value.push(self.escape_sequence()?),
+                Some(c) => {
+                    if c == '\n' {
+                        self.line += 1;
+                    }
+                    value.push(c);
+                }
+                None => {
+                    return Err(Error::Lexical(LexerError {
+                        line,
+                        message: "Unterminated string.".to_string(),
+                    }))
+                }
             }
-            self.advance_char();
-            offset += 1;
         }
-        // The closing "
-        self.advance_char();
-        offset += 1;
 
-        // Add check if eof without closing the string
-        let lexeme = &self.source[self.start..(self.start + offset)];
-        let literal = &lexeme[1..(lexeme.len() - 1)];
+        Ok(Token::new_full(
+            kind,
+            &self.source[self.start..self.cursor],
+            Literal::String(value),
+            self.start,
+            line,
+        ))
+    }
 
-        StringData {
-            lexeme,
-            literal,
-            start: self.start,
+    // The opening /* is already consumed. Block comments nest, so every
+    // /* inside needs its own */.
+    fn block_comment(&mut self) -> LoxResult<Token> {
+        let line = self.line;
+        let mut depth = 1;
+        while depth > 0 {
+            match self.advance_char() {
+                Some('/') if self.advance_if('*') => depth += 1,
+                Some('*') if self.advance_if('/') => depth -= 1,
+                Some('\n') => self.line += 1,
+                Some(_) => (),
+                None => {
+                    return Err(Error::Lexical(LexerError {
+                        line,
+                        message: "Unterminated block comment.".to_string(),
+                    }))
+                }
+            }
+        }
+        Ok(self.new_token(TokenKind::Comment))
+    }
+
+    // The backslash is already consumed
+    fn escape_sequence(&mut self) -> LoxResult<char> {
+        match self.advance_char() {
+            Some('n') => Ok('\n'),
+            Some('t') => Ok('\t'),
+            Some('"') => Ok('"'),
+            Some('\\') => Ok('\\'),
+            Some('$') => Ok('$'),
+            Some('u') => self.unicode_escape(),
+            Some(c) => Err(self.error(&format!("Invalid escape sequence '\\{}'.", c))),
+            None => Err(self.error("Unterminated string.")),
         }
     }
 
+    // `\u{...}` with one to six hex digits naming a Unicode scalar value
+    fn unicode_escape(&mut self) -> LoxResult<char> {
+        let message = "Invalid unicode escape sequence.";
+        if !self.advance_if('{') {
+            return Err(self.error(message));
+        }
+        let mut digits = String::new();
+        while self.peek_first().is_ascii_hexdigit() && !self.is_eof() {
+            digits.extend(self.advance_char());
+        }
+        if !self.advance_if('}') || digits.is_empty() || digits.len() > 6 {
+            return Err(self.error(message));
+        }
+        u32::from_str_radix(&digits, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| self.error(message))
+    }
+
+    fn error(&self, message: &str) -> Error {
+        Error::Lexical(LexerError {
+            line: self.line,
+            message: message.to_string(),
+        })
+    }
+
     fn number(&mut self, first_digit: char) -> NumberData<'a> {
         let mut offset: usize = 1;
         let chars = once(first_digit).chain(self.chars.clone());
@@ -162,14 +253,11 @@ impl<'a> Lexer<'a> {
     }
 
     fn identifier(&mut self) -> StringData<'a> {
-        // Take into account already consumed byte
-        let start = self.cursor - 1;
-        let mut offset: usize = 1;
-        while self.peek_first().is_alphanumeric() {
+        let start = self.start;
+        while is_alphanumeric(self.peek_first()) {
             self.advance_char();
-            offset += 1;
         }
-        let identifier = &self.source[start..(start + offset)];
+        let identifier = &self.source[start..self.cursor];
         StringData {
             lexeme: identifier,
             literal: identifier,
@@ -177,7 +265,7 @@ impl<'a> Lexer<'a> {
         }
     }
 
-    fn new_token(&self, token_kind: TokenKind) -> Token<'a> {
+    fn new_token(&self, token_kind: TokenKind) -> Token {
         Token::new(
             token_kind,
             &self.source[self.start..self.cursor],
@@ -186,7 +274,7 @@ impl<'a> Lexer<'a> {
         )
     }
 
-    pub fn advance_token(&mut self) -> LoxResult<'a, Token<'a>> {
+    pub fn advance_token(&mut self) -> LoxResult<Token> {
         let mut first_char: Option<char>;
         loop {
             first_char = self.advance_char();
@@ -194,12 +282,7 @@ impl<'a> Lexer<'a> {
                 Some(c) => c,
                 None => {
                     self.saw_eof = true;
-                    return Ok(Token::new(
-                        TokenKind::Eof,
-                        EOF_STR,
-                        self.length,
-                        self.line + 1,
-                    ));
+                    return Ok(Token::new(TokenKind::Eof, EOF_STR, self.length, self.line));
                 }
             };
             match c {
@@ -213,19 +296,73 @@ impl<'a> Lexer<'a> {
             }
         }
         let first_char = first_char.unwrap();
-        self.start = self.cursor - 1;
+        self.start = self.cursor - first_char.len_utf8();
 
         let token = match first_char {
             '(' => self.new_token(TokenKind::LeftParen),
             ')' => self.new_token(TokenKind::RightParen),
-            '{' => self.new_token(TokenKind::LeftBrace),
-            '}' => self.new_token(TokenKind::RightBrace),
+            '{' => {
+                if let Some(depth) = self.interpolations.last_mut() {
+                    *depth += 1;
+                }
+                self.new_token(TokenKind::LeftBrace)
+            }
+            '}' => match self.interpolations.last_mut() {
+                Some(0) => {
+                    self.interpolations.pop();
+                    self.string()?
+                }
+                Some(depth) => {
+                    *depth -= 1;
+                    self.new_token(TokenKind::RightBrace)
+                }
+                None => self.new_token(TokenKind::RightBrace),
+            },
+            '[' => self.new_token(TokenKind::LeftBracket),
+            ':' => self.new_token(TokenKind::Colon),
+            '?' => self.new_token(TokenKind::Question),
+            ']' => self.new_token(TokenKind::RightBracket),
             ',' => self.new_token(TokenKind::Comma),
-            '.' => self.new_token(TokenKind::Dot),
-            '-' => self.new_token(TokenKind::Minus),
-            '+' => self.new_token(TokenKind::Plus),
+            '.' => {
+                if self.peek_first() == '.' && self.peek_second() == '.' {
+                    self.advance_char();
+                    self.advance_char();
+                    self.new_token(TokenKind::Ellipsis)
+                } else {
+                    self.new_token(TokenKind::Dot)
+                }
+            }
+            '-' => {
+                if self.advance_if('=') {
+                    self.new_token(TokenKind::MinusEqual)
+                } else if self.advance_if('-') {
+                    self.new_token(TokenKind::MinusMinus)
+                } else {
+                    self.new_token(TokenKind::Minus)
+                }
+            }
+            '+' => {
+                if self.advance_if('=') {
+                    self.new_token(TokenKind::PlusEqual)
+                } else if self.advance_if('+') {
+                    self.new_token(TokenKind::PlusPlus)
+                } else {
+                    self.new_token(TokenKind::Plus)
+                }
+            }
             ';' => self.new_token(TokenKind::Semicolon),
-            '*' => self.new_token(TokenKind::Star),
+            '%' => self.new_token(TokenKind::Percent),
+            '*' => {
+                if self.advance_if('*') {
+                    self.new_token(TokenKind::StarStar)
+                } else if self.advance_if('=') {
+                    self.new_token(TokenKind::StarEqual)
+                } else {
+                    self.new_token(TokenKind::Star)
+                }
+            }
+            // `//` starts a comment, so integer division is spelled `~/`
+            '~' if self.advance_if('/') => self.new_token(TokenKind::TildeSlash),
 
             '!' => {
                 if self.advance_if('=') {
@@ -261,22 +398,16 @@ impl<'a> Lexer<'a> {
                     // Comment goes until the end of the line
                     self.eat_while(|c| c != '\n');
                     self.new_token(TokenKind::Comment)
+                } else if self.advance_if('*') {
+                    self.block_comment()?
+                } else if self.advance_if('=') {
+                    self.new_token(TokenKind::SlashEqual)
                 } else {
                     self.new_token(TokenKind::Slash)
                 }
             }
 
-            '"' => {
-                let line = self.line;
-                let s = self.string();
-                Token::new_full(
-                    TokenKind::String,
-                    s.lexeme,
-                    Literal::String(s.literal.to_string()),
-                    s.start,
-                    line,
-                )
-            }
+            '"' => self.string()?,
 
             c if c.is_ascii_digit() => {
                 let line = self.line;
@@ -332,17 +463,12 @@ impl<'a> Lexer<'a> {
                 }
             }
 
-            c => {
-                return Err(Error::Lexical(LexerError {
-                    line: self.line,
-                    message: format!("Unexpected char: {}", c),
-                }))
-            }
+            c => return Err(self.error(&format!("Unexpected char: {}", c))),
         };
         Ok(token)
     }
 
-    pub fn tokenize(&mut self) -> LoxResult<'a, Vec<Token<'a>>> {
+    pub fn tokenize(&mut self) -> LoxResult<Vec<Token>> {
         let mut tokens: Vec<Token> = Vec::new();
         loop {
             let token = self.advance_token()?;
@@ -360,7 +486,7 @@ impl<'a> Lexer<'a> {
 }
 
 // impl<'a> Iterator for Lexer<'a> {
-//     type Item = LoxResult<Token<'a>>;
+//     type Item = LoxResult<Token>;
 //
 //     fn next(&mut self) -> Option<Self::Item> {
 //         if self.saw_eof {
@@ -371,6 +497,11 @@ impl<'a> Lexer<'a> {
 //     }
 // }
 
-fn is_alpha(c: char) -> bool {
+pub(crate) fn is_alpha(c: char) -> bool {
     c.is_ascii_lowercase() || c.is_ascii_uppercase() || c == '_'
 }
+
+// Characters that can follow the first one of an identifier
+pub(crate) fn is_alphanumeric(c: char) -> bool {
+    is_alpha(c) || c.is_alphanumeric()
+}
@@ -6,7 +6,13 @@ pub mod interpreter;
 pub mod lexer;
 pub mod lox;
 pub mod lox_callable;
+pub mod lox_class;
+pub mod lox_function;
+pub mod lox_instance;
+pub mod lox_module;
+pub mod natives;
 pub mod parser;
+pub mod resolver;
 pub mod stmt;
 pub mod token;
 pub mod visitor;
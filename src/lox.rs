@@ -1,17 +1,25 @@
-use crate::ast_printer::AstPrinter;
 use crate::error::{Error, LoxResult};
 use crate::interpreter::Interpreter;
 use crate::lexer::Lexer;
 use crate::parser::Parser;
+use crate::resolver::Resolver;
+use crate::stmt::Stmt;
 use crate::token::Token;
 use std::fs;
 use std::io;
 use std::io::Write;
+use std::path::Path;
 
 pub struct Lox {
     interpreter: Interpreter,
 }
 
+impl Default for Lox {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Lox {
     pub fn new() -> Self {
         Self {
@@ -19,10 +27,20 @@ impl Lox {
         }
     }
 
+    // Runs scripts printing to `output` instead of stdout
+    pub fn with_output(output: impl Write + 'static) -> Self {
+        Self {
+            interpreter: Interpreter::with_output(Box::new(output)),
+        }
+    }
+
     pub fn run_file(&mut self, file_path: &str) -> LoxResult<()> {
         let source = fs::read_to_string(file_path).expect("should have been able to read the file");
-        self.run(&source).unwrap();
-        Ok(())
+        // Imports in the script are relative to it
+        self.interpreter.enter_file(Path::new(file_path));
+        let result = self.run(&source);
+        self.interpreter.leave_file();
+        result
     }
 
     pub fn run_prompt(&mut self) {
@@ -44,7 +62,9 @@ impl Lox {
                     if line == "exit()" {
                         break;
                     }
-                    self.run(&line).unwrap();
+                    if let Err(err) = self.run(&line) {
+                        eprintln!("{}", err);
+                    }
                     line.clear();
                 }
                 Err(error) => panic!("something went wrong: {}", error),
@@ -52,20 +72,13 @@ impl Lox {
         }
     }
 
-    fn run<'a>(&mut self, source: &'a str) -> LoxResult<'a, ()> {
-        let mut lexer = Lexer::new(source);
-        let tokens: Vec<Token> = lexer.tokenize()?;
-        // for token in tokens {
-        //     println!("{}", token);
-        // }
-        // let mut ast_printer = AstPrinter;
-        let mut parser = Parser::new(tokens);
-        let statements = parser.parse();
+    fn run(&mut self, source: &str) -> LoxResult<()> {
+        let statements = compile(source, &mut self.interpreter)?;
         // match expr {
         //     Some(e) => println!("{}", ast_printer.print(&e)),
         //     None => println!("Error when parsing tokens"),
         // }
-        self.interpreter.interpret(statements);
+        self.interpreter.interpret(statements)?;
         // match expr {
         //     Some(e) => {
         //         self.interpreter.interpret(statements);
@@ -76,3 +89,17 @@ impl Lox {
         Ok(())
     }
 }
+
+// Lexes, parses and resolves `source` for `interpreter` to run
+pub(crate) fn compile(source: &str, interpreter: &mut Interpreter) -> LoxResult<Vec<Stmt>> {
+    let mut lexer = Lexer::new(source);
+    let tokens: Vec<Token> = lexer.tokenize()?;
+    // for token in tokens {
+    //     println!("{}", token);
+    // }
+    // let mut ast_printer = AstPrinter;
+    let mut parser = Parser::new(tokens);
+    let statements = parser.parse().map_err(Error::Syntactic)?;
+    Resolver::new(interpreter).resolve(&statements)?;
+    Ok(statements)
+}
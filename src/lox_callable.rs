@@ -1,9 +1,48 @@
-use crate::expr::Expr;
+use crate::error::LoxResult;
 use crate::interpreter::Interpreter;
-use crate::token::Literal;
-use std::fmt::Debug;
+use crate::token::{Literal, Token};
+use std::fmt;
+use std::fmt::{Debug, Display};
+use std::rc::Rc;
 
-pub trait LoxCallable: Debug + Clone + PartialEq {
-    fn arity(&self) -> usize;
-    fn call(&self, interpreter: &mut Interpreter, arguments: &[Box<Expr>]) -> Literal;
+// How many arguments a callable accepts. There is no upper bound
+// for functions with a rest parameter.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Arity {
+    pub min: usize,
+    pub max: Option<usize>,
+}
+
+impl Arity {
+    pub fn exactly(count: usize) -> Self {
+        Self {
+            min: count,
+            max: Some(count),
+        }
+    }
+
+    pub fn accepts(&self, count: usize) -> bool {
+        count >= self.min && self.max.is_none_or(|max| count <= max)
+    }
+}
+
+impl Display for Arity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.max {
+            Some(max) if max == self.min => write!(f, "{}", max),
+            Some(max) => write!(f, "{} to {}", self.min, max),
+            None => write!(f, "at least {}", self.min),
+        }
+    }
+}
+
+pub trait LoxCallable: Debug + Display {
+    fn arity(&self) -> Arity;
+    fn call(
+        self: Rc<Self>,
+        interpreter: &mut Interpreter,
+        // Where the call happened, for error reporting
+        paren: &Token,
+        arguments: Vec<Literal>,
+    ) -> LoxResult<Literal>;
 }
@@ -0,0 +1,68 @@
+use crate::error::LoxResult;
+use crate::interpreter::Interpreter;
+use crate::lox_callable::{Arity, LoxCallable};
+use crate::lox_function::LoxFunction;
+use crate::lox_instance::LoxInstance;
+use crate::token::{Literal, Token};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+#[derive(Debug)]
+pub struct LoxClass {
+    pub name: String,
+    superclass: Option<Rc<LoxClass>>,
+    methods: HashMap<String, Rc<LoxFunction>>,
+}
+
+impl LoxClass {
+    pub fn new(
+        name: String,
+        superclass: Option<Rc<LoxClass>>,
+        methods: HashMap<String, Rc<LoxFunction>>,
+    ) -> Self {
+        Self {
+            name,
+            superclass,
+            methods,
+        }
+    }
+
+    // Methods not defined on the class itself are inherited
+    pub fn find_method(&self, name: &str) -> Option<Rc<LoxFunction>> {
+        match self.methods.get(name) {
+            Some(method) => Some(Rc::clone(method)),
+            None => self
+                .superclass
+                .as_ref()
+                .and_then(|superclass| superclass.find_method(name)),
+        }
+    }
+}
+
+impl LoxCallable for LoxClass {
+    fn arity(&self) -> Arity {
+        self.find_method("init")
+            .map_or(Arity::exactly(0), |initializer| initializer.arity())
+    }
+
+    fn call(
+        self: Rc<Self>,
+        interpreter: &mut Interpreter,
+        paren: &Token,
+        arguments: Vec<Literal>,
+    ) -> LoxResult<Literal> {
+        let instance = Literal::Instance(Rc::new(RefCell::new(LoxInstance::new(Rc::clone(&self)))));
+        if let Some(initializer) = self.find_method("init") {
+            Rc::new(initializer.bind(instance.clone())).call(interpreter, paren, arguments)?;
+        }
+        Ok(instance)
+    }
+}
+
+impl fmt::Display for LoxClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
@@ -0,0 +1,115 @@
+use crate::environment::Environment;
+use crate::error::{Error, LoxResult};
+use crate::interpreter::Interpreter;
+use crate::lox_callable::{Arity, LoxCallable};
+use crate::stmt::FunctionDecl;
+use crate::token::{Literal, Token, TokenKind};
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+pub struct LoxFunction {
+    declaration: Rc<FunctionDecl>,
+    // The environment active when the function was declared
+    closure: Rc<RefCell<Environment>>,
+    is_initializer: bool,
+}
+
+impl LoxFunction {
+    pub fn new(
+        declaration: Rc<FunctionDecl>,
+        closure: Rc<RefCell<Environment>>,
+        is_initializer: bool,
+    ) -> Self {
+        Self {
+            declaration,
+            closure,
+            is_initializer,
+        }
+    }
+
+    // Turns a method into a function whose `this` is `instance`
+    pub fn bind(&self, instance: Literal) -> LoxFunction {
+        let mut environment = Environment::new(Rc::clone(&self.closure));
+        environment.define_implicit("this".to_string(), instance);
+        LoxFunction::new(
+            Rc::clone(&self.declaration),
+            Rc::new(RefCell::new(environment)),
+            self.is_initializer,
+        )
+    }
+}
+
+impl LoxCallable for LoxFunction {
+    fn arity(&self) -> Arity {
+        let params = &self.declaration.params;
+        Arity {
+            min: params
+                .iter()
+                .take_while(|param| param.default.is_none())
+                .count(),
+            max: match self.declaration.rest {
+                Some(_) => None,
+                None => Some(params.len()),
+            },
+        }
+    }
+
+    fn call(
+        self: Rc<Self>,
+        interpreter: &mut Interpreter,
+        _paren: &Token,
+        arguments: Vec<Literal>,
+    ) -> LoxResult<Literal> {
+        let environment = Rc::new(RefCell::new(Environment::new(Rc::clone(&self.closure))));
+        let mut arguments = arguments.into_iter();
+        for param in &self.declaration.params {
+            let value = match (arguments.next(), &param.default) {
+                (Some(argument), _) => argument,
+                // The arity check leaves out only parameters with defaults
+                (None, Some(default)) => {
+                    interpreter.evaluate_in(default, Rc::clone(&environment))?
+                }
+                (None, None) => Literal::Nil,
+            };
+            environment.borrow_mut().define(&param.name, value)?;
+        }
+        if let Some(rest) = &self.declaration.rest {
+            let rest_arguments = Literal::List(Rc::new(RefCell::new(arguments.collect())));
+            environment.borrow_mut().define(rest, rest_arguments)?;
+        }
+        let value = match interpreter.execute_block(&self.declaration.body, environment) {
+            Ok(()) => Literal::Nil,
+            Err(Error::Return(value)) => value,
+            Err(err) => return Err(err),
+        };
+
+        // `init()` always hands back the instance, even when called directly
+        if self.is_initializer {
+            return Ok(self
+                .closure
+                .borrow()
+                .get_at(0, "this")
+                .unwrap_or(Literal::Nil));
+        }
+        Ok(value)
+    }
+}
+
+impl fmt::Display for LoxFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.declaration.name.kind {
+            TokenKind::Fun => write!(f, "<fn>"),
+            _ => write!(f, "<fn {}>", self.declaration.name.lexeme),
+        }
+    }
+}
+
+// The closure can hold the function itself, so it is left out
+impl fmt::Debug for LoxFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LoxFunction")
+            .field("declaration", &self.declaration)
+            .finish_non_exhaustive()
+    }
+}
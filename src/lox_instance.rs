@@ -0,0 +1,59 @@
+use crate::error::{Error, LoxResult, RuntimeError};
+use crate::lox_class::LoxClass;
+use crate::token::{Literal, Token};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+pub struct LoxInstance {
+    class: Rc<LoxClass>,
+    fields: HashMap<String, Literal>,
+}
+
+impl LoxInstance {
+    pub fn new(class: Rc<LoxClass>) -> Self {
+        Self {
+            class,
+            fields: HashMap::new(),
+        }
+    }
+
+    // Fields shadow methods. Methods are bound to `instance` so they
+    // keep their `this` after being pulled off the object.
+    pub fn get(instance: &Rc<RefCell<LoxInstance>>, name: &Token) -> LoxResult<Literal> {
+        if let Some(value) = instance.borrow().fields.get(&*name.lexeme) {
+            return Ok(value.clone());
+        }
+
+        let method = instance.borrow().class.find_method(&name.lexeme);
+        match method {
+            Some(method) => Ok(Literal::Callable(Rc::new(
+                method.bind(Literal::Instance(Rc::clone(instance))),
+            ))),
+            None => Err(Error::RunTime(RuntimeError {
+                token: name.clone(),
+                message: format!("Undefined property '{}'.", name.lexeme),
+            })),
+        }
+    }
+
+    pub fn set(&mut self, name: &str, value: Literal) {
+        self.fields.insert(name.to_string(), value);
+    }
+}
+
+impl fmt::Display for LoxInstance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} instance", self.class.name)
+    }
+}
+
+// Fields can point back at the instance, so they are left out
+impl fmt::Debug for LoxInstance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LoxInstance")
+            .field("class", &self.class.name)
+            .finish_non_exhaustive()
+    }
+}
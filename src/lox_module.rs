@@ -0,0 +1,49 @@
+use crate::environment::Environment;
+use crate::error::{Error, LoxResult, RuntimeError};
+use crate::token::{Literal, Token};
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+// An imported file. Its top-level definitions are read off the
+// environment it ran in, so later changes to them show through.
+pub struct LoxModule {
+    pub name: String,
+    globals: Rc<RefCell<Environment>>,
+}
+
+impl LoxModule {
+    pub fn new(name: String, globals: Rc<RefCell<Environment>>) -> Self {
+        Self { name, globals }
+    }
+
+    pub fn get(&self, name: &Token) -> LoxResult<Literal> {
+        self.globals
+            .borrow()
+            .get_at(0, &name.lexeme)
+            .ok_or_else(|| {
+                Error::RunTime(RuntimeError {
+                    token: name.clone(),
+                    message: format!(
+                        "Undefined property '{}' in module '{}'.",
+                        name.lexeme, self.name
+                    ),
+                })
+            })
+    }
+}
+
+impl fmt::Display for LoxModule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<module {}>", self.name)
+    }
+}
+
+// The globals point back at every function defined in the module
+impl fmt::Debug for LoxModule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LoxModule")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
@@ -1,3 +1,4 @@
+use rlox::error::Error;
 use rlox::lox::Lox;
 use std::env;
 use std::process::ExitCode;
@@ -11,7 +12,13 @@ fn main() -> ExitCode {
     let mut lox = Lox::new();
     if args.len() == 2 {
         println!("Running file: {}", args[1]);
-        lox.run_file(&args[1]).unwrap();
+        if let Err(err) = lox.run_file(&args[1]) {
+            eprintln!("{}", err);
+            return match err.cause() {
+                Error::RunTime(_) | Error::Throw { .. } => ExitCode::from(70),
+                _ => ExitCode::from(65),
+            };
+        }
     } else {
         println!("Running prompt");
         lox.run_prompt();
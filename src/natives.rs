@@ -0,0 +1,237 @@
+use crate::environment::Environment;
+use crate::error::LoxResult;
+use crate::interpreter::{runtime_error, Interpreter};
+use crate::lox_callable::{Arity, LoxCallable};
+use crate::token::{Literal, MapKey, Range, Token};
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use unicode_segmentation::UnicodeSegmentation;
+
+type NativeFn = fn(&Token, Vec<Literal>) -> LoxResult<Literal>;
+
+// A function implemented in Rust. The interpreter checks the arity,
+// so `function` can rely on getting as many arguments as it allows.
+#[derive(Debug)]
+pub struct NativeFunction {
+    name: &'static str,
+    arity: Arity,
+    function: NativeFn,
+}
+
+impl LoxCallable for NativeFunction {
+    fn arity(&self) -> Arity {
+        self.arity
+    }
+
+    fn call(
+        self: Rc<Self>,
+        _interpreter: &mut Interpreter,
+        paren: &Token,
+        arguments: Vec<Literal>,
+    ) -> LoxResult<Literal> {
+        (self.function)(paren, arguments)
+    }
+}
+
+impl fmt::Display for NativeFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<native fn>")
+    }
+}
+
+pub fn define(globals: &mut Environment) {
+    let natives = [
+        NativeFunction {
+            name: "clock",
+            arity: Arity::exactly(0),
+            function: clock,
+        },
+        NativeFunction {
+            name: "len",
+            arity: Arity::exactly(1),
+            function: len,
+        },
+        NativeFunction {
+            name: "push",
+            arity: Arity::exactly(2),
+            function: push,
+        },
+        NativeFunction {
+            name: "pop",
+            arity: Arity::exactly(1),
+            function: pop,
+        },
+        NativeFunction {
+            name: "insert",
+            arity: Arity::exactly(3),
+            function: insert,
+        },
+        NativeFunction {
+            name: "range",
+            arity: Arity {
+                min: 2,
+                max: Some(3),
+            },
+            function: range,
+        },
+        NativeFunction {
+            name: "has",
+            arity: Arity::exactly(2),
+            function: has,
+        },
+        NativeFunction {
+            name: "keys",
+            arity: Arity::exactly(1),
+            function: keys,
+        },
+        NativeFunction {
+            name: "values",
+            arity: Arity::exactly(1),
+            function: values,
+        },
+        NativeFunction {
+            name: "remove",
+            arity: Arity::exactly(2),
+            function: remove,
+        },
+    ];
+    for native in natives {
+        globals.define_implicit(native.name.to_string(), Literal::Callable(Rc::new(native)));
+    }
+}
+
+// Checks that `index` is a whole number in `0..len`
+pub(crate) fn list_index(token: &Token, index: &Literal, len: usize) -> LoxResult<usize> {
+    let Literal::Number(number) = index else {
+        return Err(runtime_error(token, "List index must be a number."));
+    };
+    if number.fract() != 0.0 {
+        return Err(runtime_error(token, "List index must be an integer."));
+    }
+    if *number < 0.0 || *number >= len as f64 {
+        return Err(runtime_error(
+            token,
+            &format!("List index {} out of bounds for length {}.", number, len),
+        ));
+    }
+    Ok(*number as usize)
+}
+
+pub(crate) fn map_key(token: &Token, key: &Literal) -> LoxResult<MapKey> {
+    MapKey::from_literal(key)
+        .ok_or_else(|| runtime_error(token, "Map keys must be strings or numbers."))
+}
+
+fn list(elements: Vec<Literal>) -> Literal {
+    Literal::List(Rc::new(RefCell::new(elements)))
+}
+
+fn clock(_paren: &Token, _arguments: Vec<Literal>) -> LoxResult<Literal> {
+    // A clock set before the epoch reads as 0 rather than failing the script
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    Ok(Literal::Number((time / 1000) as f64))
+}
+
+fn len(paren: &Token, arguments: Vec<Literal>) -> LoxResult<Literal> {
+    match arguments.as_slice() {
+        [Literal::List(list)] => Ok(Literal::Number(list.borrow().len() as f64)),
+        [Literal::Map(map)] => Ok(Literal::Number(map.borrow().len() as f64)),
+        [Literal::String(s)] => Ok(Literal::Number(s.graphemes(true).count() as f64)),
+        _ => Err(runtime_error(
+            paren,
+            "len() expects a list, a map or a string.",
+        )),
+    }
+}
+
+fn push(paren: &Token, arguments: Vec<Literal>) -> LoxResult<Literal> {
+    match arguments.as_slice() {
+        [Literal::List(list), value] => {
+            list.borrow_mut().push(value.clone());
+            Ok(Literal::Nil)
+        }
+        _ => Err(runtime_error(paren, "push() expects a list.")),
+    }
+}
+
+fn pop(paren: &Token, arguments: Vec<Literal>) -> LoxResult<Literal> {
+    match arguments.as_slice() {
+        [Literal::List(list)] => list
+            .borrow_mut()
+            .pop()
+            .ok_or_else(|| runtime_error(paren, "Can't pop from an empty list.")),
+        _ => Err(runtime_error(paren, "pop() expects a list.")),
+    }
+}
+
+fn insert(paren: &Token, arguments: Vec<Literal>) -> LoxResult<Literal> {
+    match arguments.as_slice() {
+        [Literal::List(list), index, value] => {
+            // Inserting right after the last element appends
+            let len = list.borrow().len();
+            let index = if *index == Literal::Number(len as f64) {
+                len
+            } else {
+                list_index(paren, index, len)?
+            };
+            list.borrow_mut().insert(index, value.clone());
+            Ok(Literal::Nil)
+        }
+        _ => Err(runtime_error(paren, "insert() expects a list.")),
+    }
+}
+
+fn has(paren: &Token, arguments: Vec<Literal>) -> LoxResult<Literal> {
+    match arguments.as_slice() {
+        [Literal::Map(map), key] => {
+            let key = map_key(paren, key)?;
+            Ok(Literal::Bool(map.borrow().contains_key(&key)))
+        }
+        _ => Err(runtime_error(paren, "has() expects a map.")),
+    }
+}
+
+fn keys(paren: &Token, arguments: Vec<Literal>) -> LoxResult<Literal> {
+    match arguments.as_slice() {
+        [Literal::Map(map)] => Ok(list(map.borrow().keys().map(MapKey::to_literal).collect())),
+        _ => Err(runtime_error(paren, "keys() expects a map.")),
+    }
+}
+
+fn values(paren: &Token, arguments: Vec<Literal>) -> LoxResult<Literal> {
+    match arguments.as_slice() {
+        [Literal::Map(map)] => Ok(list(map.borrow().values().cloned().collect())),
+        _ => Err(runtime_error(paren, "values() expects a map.")),
+    }
+}
+
+// Returns the removed value, or nil if the key wasn't there
+fn remove(paren: &Token, arguments: Vec<Literal>) -> LoxResult<Literal> {
+    match arguments.as_slice() {
+        [Literal::Map(map), key] => {
+            let key = map_key(paren, key)?;
+            Ok(map.borrow_mut().remove(&key).unwrap_or(Literal::Nil))
+        }
+        _ => Err(runtime_error(paren, "remove() expects a map.")),
+    }
+}
+
+// The step is optional and defaults to 1
+fn range(paren: &Token, arguments: Vec<Literal>) -> LoxResult<Literal> {
+    let (start, end, step) = match arguments.as_slice() {
+        [Literal::Number(start), Literal::Number(end)] => (*start, *end, 1.0),
+        [Literal::Number(start), Literal::Number(end), Literal::Number(step)] => {
+            (*start, *end, *step)
+        }
+        _ => return Err(runtime_error(paren, "range() expects numbers.")),
+    };
+    if step == 0.0 {
+        return Err(runtime_error(paren, "range() step can't be 0."));
+    }
+    Ok(Literal::Range(Range { start, end, step }))
+}
@@ -1,90 +1,199 @@
-use crate::error::{Error, LoxResult, ParserError};
-use crate::expr::Expr;
-use crate::stmt::Stmt;
-use crate::stmt::Stmt::Print;
+use crate::error::ParserError;
+use crate::expr::{Expr, ExprId};
+use crate::stmt::{FunctionDecl, Param, Stmt};
 use crate::token::{Literal, Token, TokenKind};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
-pub struct Parser<'a> {
-    tokens: Vec<Token<'a>>,
+// Shared by every parser so that ids stay unique across all the lines and
+// files run by one interpreter.
+static NEXT_EXPR_ID: AtomicUsize = AtomicUsize::new(0);
+
+pub struct Parser {
+    tokens: Vec<Token>,
     current: usize,
+    // How many function bodies enclose the current token
+    function_depth: usize,
+    // How many loops enclose the current token, within the current function
+    loop_depth: usize,
+    errors: Vec<ParserError>,
 }
 
-impl<'a> Parser<'a> {
-    pub fn new(tokens: Vec<Token<'a>>) -> Self {
-        Self { tokens, current: 0 }
+type ParseResult<T> = Result<T, ParserError>;
+
+impl Parser {
+    pub fn new(tokens: Vec<Token>) -> Self {
+        Self {
+            tokens,
+            current: 0,
+            function_depth: 0,
+            loop_depth: 0,
+            errors: Vec::new(),
+        }
     }
 
-    // pub fn parse(&mut self) -> Option<Expr<'a>> {
+    // pub fn parse(&mut self) -> Option<Expr> {
     //     self.expression().ok()
     // }
-    pub fn parse(&mut self) -> Vec<Stmt<'a>> {
+    // Keeps going after a syntax error so that every error in the source
+    // is reported at once. Statements are only returned when there were none.
+    pub fn parse(&mut self) -> Result<Vec<Stmt>, Vec<ParserError>> {
         let mut statements: Vec<Stmt> = Vec::new();
         while !self.is_eof() {
-            statements.push(self.declaration());
+            if let Some(stmt) = self.declaration() {
+                statements.push(stmt);
+            }
+        }
+
+        if self.errors.is_empty() {
+            Ok(statements)
+        } else {
+            Err(std::mem::take(&mut self.errors))
         }
-        statements
     }
 
-    fn declaration(&mut self) -> Stmt<'a> {
-        if self.match_(&[TokenKind::Fun]) {
-            self.function("function")
+    // Statement boundary where parsing recovers from a syntax error
+    fn declaration(&mut self) -> Option<Stmt> {
+        let stmt = if self.match_(&[TokenKind::Class]) {
+            self.class_declaration()
+        } else if self.check(&TokenKind::Fun) && self.check_next(&TokenKind::Identifier) {
+            // Without a name it is a lambda starting an expression statement
+            self.advance();
+            self.function("function").map(Stmt::Function)
         } else if self.match_(&[TokenKind::Var]) {
             self.var_declaration()
+        } else if self.match_(&[TokenKind::Const]) {
+            self.const_declaration()
         } else {
             self.statement()
+        };
+
+        match stmt {
+            Ok(stmt) => Some(stmt),
+            Err(err) => {
+                self.errors.push(err);
+                self.synchronize();
+                None
+            }
+        }
+    }
+
+    fn class_declaration(&mut self) -> ParseResult<Stmt> {
+        let name = self.consume(TokenKind::Identifier, "Expect class name.")?;
+
+        let mut superclass = None;
+        if self.match_(&[TokenKind::Less]) {
+            self.consume(TokenKind::Identifier, "Expect superclass name.")?;
+            superclass = Some(Expr::Variable {
+                id: next_expr_id(),
+                name: self.previous(),
+            });
+        }
+
+        self.consume(TokenKind::LeftBrace, "Expect '{' before class body.")?;
+
+        let mut methods = Vec::new();
+        while !self.check(&TokenKind::RightBrace) && !self.is_eof() {
+            methods.push(self.function("method")?);
         }
+
+        self.consume(TokenKind::RightBrace, "Expect '}' after class body.")?;
+        Ok(Stmt::Class {
+            name,
+            superclass,
+            methods,
+        })
+    }
+
+    fn function(&mut self, kind: &str) -> ParseResult<Rc<FunctionDecl>> {
+        let name = self.consume(TokenKind::Identifier, &format!("Expect {} name.", kind))?;
+        self.function_body(kind, name)
     }
 
-    fn function(&mut self, kind: &str) -> Stmt<'a> {
-        let name = self
-            .consume(TokenKind::Identifier, &format!("Expect {} name.", kind))
-            .unwrap();
+    // Everything after the name, shared with lambdas
+    fn function_body(&mut self, kind: &str, name: Token) -> ParseResult<Rc<FunctionDecl>> {
         self.consume(
             TokenKind::LeftParen,
             &format!("Expect '(' after {} name.", kind),
-        )
-        .unwrap();
-        let mut params = Vec::new();
+        )?;
+        let mut params: Vec<Param> = Vec::new();
+        let mut rest = None;
         if !self.check(&TokenKind::RightParen) {
-            params.push(
-                self.consume(TokenKind::Identifier, "Expect parameter name.")
-                    .unwrap(),
-            );
-            while self.match_(&[TokenKind::Comma]) {
+            loop {
                 if params.len() >= 255 {
-                    eprintln!("Can't have more than 255 parameters.");
+                    let token = self.peek().cloned().unwrap();
+                    self.error(token, "Can't have more than 255 parameters.");
+                }
+                if self.match_(&[TokenKind::Ellipsis]) {
+                    rest =
+                        Some(self.consume(TokenKind::Identifier, "Expect rest parameter name.")?);
+                    // Nothing may follow the rest parameter, not even a comma
+                    break;
+                }
+                let name = self.consume(TokenKind::Identifier, "Expect parameter name.")?;
+                let default = if self.match_(&[TokenKind::Equal]) {
+                    Some(self.expression()?)
+                } else {
+                    if params.last().is_some_and(|param| param.default.is_some()) {
+                        self.error(
+                            name.clone(),
+                            "Parameters after one with a default value need one too.",
+                        );
+                    }
+                    None
+                };
+                params.push(Param { name, default });
+                if !self.match_(&[TokenKind::Comma]) {
+                    break;
                 }
-                params.push(
-                    self.consume(TokenKind::Identifier, "Expect parameter name.")
-                        .unwrap(),
-                );
             }
         }
-        self.consume(TokenKind::RightParen, "Expect ')' after parameters")
-            .unwrap();
+        self.consume(TokenKind::RightParen, "Expect ')' after parameters")?;
+        self.consume(
+            TokenKind::LeftBrace,
+            &format!("Expect '{{' before {} body.", kind),
+        )?;
+        // A loop around the declaration doesn't make `break` valid inside the body
+        let enclosing_loop_depth = std::mem::take(&mut self.loop_depth);
+        self.function_depth += 1;
         let body = self.block();
-        Stmt::Function { name, params, body }
+        self.function_depth -= 1;
+        self.loop_depth = enclosing_loop_depth;
+        Ok(Rc::new(FunctionDecl {
+            name,
+            params,
+            rest,
+            body: body?,
+        }))
     }
 
-    fn var_declaration(&mut self) -> Stmt<'a> {
-        let name = self
-            .consume(TokenKind::Identifier, "Expect variable name")
-            .unwrap();
+    fn var_declaration(&mut self) -> ParseResult<Stmt> {
+        let name = self.consume(TokenKind::Identifier, "Expect variable name")?;
 
         let mut initializer = Expr::Literal(Literal::Nil);
         if self.match_(&[TokenKind::Equal]) {
-            initializer = self.expression().unwrap();
+            initializer = self.expression()?;
         }
         self.consume(
             TokenKind::Semicolon,
             "Expect ';' after variable declaration.",
-        )
-        .unwrap();
+        )?;
+
+        Ok(Stmt::Var(name, initializer))
+    }
 
-        Stmt::Var(name, initializer)
+    fn const_declaration(&mut self) -> ParseResult<Stmt> {
+        let name = self.consume(TokenKind::Identifier, "Expect constant name.")?;
+        self.consume(TokenKind::Equal, "Expect '=' after constant name.")?;
+        let initializer = self.expression()?;
+        self.consume(
+            TokenKind::Semicolon,
+            "Expect ';' after constant declaration.",
+        )?;
+        Ok(Stmt::Const(name, initializer))
     }
 
-    fn statement(&mut self) -> Stmt<'a> {
+    fn statement(&mut self) -> ParseResult<Stmt> {
         if self.match_(&[TokenKind::For]) {
             return self.for_statement();
         }
@@ -94,148 +203,341 @@ impl<'a> Parser<'a> {
         if self.match_(&[TokenKind::Print]) {
             return self.print_statement();
         }
+        if self.match_(&[TokenKind::Return]) {
+            return self.return_statement();
+        }
+        if self.match_(&[TokenKind::Break, TokenKind::Continue]) {
+            return self.loop_jump_statement();
+        }
         if self.match_(&[TokenKind::While]) {
             return self.while_statement();
         }
+        if self.match_(&[TokenKind::Import]) {
+            return self.import_statement();
+        }
+        if self.match_(&[TokenKind::Throw]) {
+            return self.throw_statement();
+        }
+        if self.match_(&[TokenKind::Try]) {
+            return self.try_statement();
+        }
+        // A statement starting with '{' is always a block. A map literal
+        // has to be wrapped in parentheses to start an expression statement.
         if self.match_(&[TokenKind::LeftBrace]) {
-            return Stmt::Block(self.block());
+            return Ok(Stmt::Block(self.block()?));
         }
         self.expression_statement()
     }
 
-    fn for_statement(&mut self) -> Stmt<'a> {
-        self.consume(TokenKind::LeftParen, "Expect '(' after 'for'.")
-            .unwrap();
+    fn for_statement(&mut self) -> ParseResult<Stmt> {
+        self.consume(TokenKind::LeftParen, "Expect '(' after 'for'.")?;
+        if self.check(&TokenKind::Var)
+            && self.check_next(&TokenKind::Identifier)
+            && matches!(self.tokens.get(self.current + 2), Some(t) if t.kind == TokenKind::In)
+        {
+            return self.for_in_statement();
+        }
+
         let initializer: Option<Stmt>;
         if self.match_(&[TokenKind::Semicolon]) {
             initializer = None;
         } else if self.match_(&[TokenKind::Var]) {
-            initializer = Some(self.var_declaration());
+            initializer = Some(self.var_declaration()?);
         } else {
-            initializer = Some(self.expression_statement());
+            initializer = Some(self.expression_statement()?);
         }
 
         let mut condition: Option<Expr> = None;
         if !self.check(&TokenKind::Semicolon) {
-            condition = Some(self.expression().unwrap());
+            condition = Some(self.expression()?);
         }
-        self.consume(TokenKind::Semicolon, "Expect ';' after loop condition.")
-            .unwrap();
+        self.consume(TokenKind::Semicolon, "Expect ';' after loop condition.")?;
 
         let mut increment: Option<Expr> = None;
         if !self.check(&TokenKind::RightParen) {
-            increment = Some(self.expression().unwrap());
+            increment = Some(self.expression()?);
         }
-        self.consume(TokenKind::RightParen, "Expect ')' after loop condition.")
-            .unwrap();
+        self.consume(TokenKind::RightParen, "Expect ')' after loop condition.")?;
 
-        let mut body = self.statement();
+        let body = self.loop_body()?;
 
-        if increment.is_some() {
-            body = Stmt::Block(vec![body, Stmt::Expression(increment.unwrap())]);
-        }
-
-        if condition.is_none() {
-            condition = Some(Expr::Literal(Literal::Bool(true)));
-        }
+        let condition = condition.unwrap_or(Expr::Literal(Literal::Bool(true)));
 
-        body = Stmt::While {
-            condition: Box::new(condition.unwrap()),
+        let mut body = Stmt::While {
+            condition: Box::new(condition),
             body: Box::new(body),
+            increment: increment.map(Box::new),
         };
 
-        if initializer.is_some() {
-            body = Stmt::Block(vec![initializer.unwrap(), body]);
+        if let Some(initializer) = initializer {
+            body = Stmt::Block(vec![initializer, body]);
         }
 
-        body
+        Ok(body)
     }
 
-    fn while_statement(&mut self) -> Stmt<'a> {
-        self.consume(TokenKind::LeftParen, "Ex[ect '(' after 'whiie'")
-            .unwrap();
-        let condition = self.expression().unwrap();
-        self.consume(TokenKind::RightParen, "Ex[ect ')' after condition")
-            .unwrap();
-        let body = self.statement();
-        Stmt::While {
+    // The opening `(` is already consumed
+    fn for_in_statement(&mut self) -> ParseResult<Stmt> {
+        self.consume(TokenKind::Var, "Expect 'var' in for-in loop.")?;
+        let name = self.consume(TokenKind::Identifier, "Expect variable name.")?;
+        let keyword = self.consume(TokenKind::In, "Expect 'in' after loop variable.")?;
+        let iterable = self.expression()?;
+        self.consume(TokenKind::RightParen, "Expect ')' after iterable.")?;
+        let body = self.loop_body()?;
+        Ok(Stmt::ForIn {
+            name,
+            keyword,
+            iterable,
+            body: Box::new(body),
+        })
+    }
+
+    fn while_statement(&mut self) -> ParseResult<Stmt> {
+        self.consume(TokenKind::LeftParen, "Expect '(' after 'while'.")?;
+        let condition = self.expression()?;
+        self.consume(TokenKind::RightParen, "Expect ')' after condition.")?;
+        let body = self.loop_body()?;
+        Ok(Stmt::While {
             condition: Box::new(condition),
             body: Box::new(body),
+            increment: None,
+        })
+    }
+
+    fn loop_body(&mut self) -> ParseResult<Stmt> {
+        self.loop_depth += 1;
+        let body = self.statement();
+        self.loop_depth -= 1;
+        body
+    }
+
+    fn loop_jump_statement(&mut self) -> ParseResult<Stmt> {
+        let keyword = self.previous();
+        if self.loop_depth == 0 {
+            self.error(
+                keyword.clone(),
+                &format!("Can't use '{}' outside of a loop.", keyword.lexeme),
+            );
+        }
+        self.consume(
+            TokenKind::Semicolon,
+            &format!("Expect ';' after '{}'.", &keyword.lexeme),
+        )?;
+
+        if keyword.kind == TokenKind::Break {
+            Ok(Stmt::Break(keyword))
+        } else {
+            Ok(Stmt::Continue(keyword))
         }
     }
 
-    fn if_statement(&mut self) -> Stmt<'a> {
-        self.consume(TokenKind::LeftParen, "Expect '(' after 'if'.")
-            .unwrap();
+    fn if_statement(&mut self) -> ParseResult<Stmt> {
+        self.consume(TokenKind::LeftParen, "Expect '(' after 'if'.")?;
 
-        let condition = self.expression().unwrap();
+        let condition = self.expression()?;
 
-        self.consume(TokenKind::RightParen, "Expect ')' after if condition.")
-            .unwrap();
+        self.consume(TokenKind::RightParen, "Expect ')' after if condition.")?;
 
-        let then_branch = Box::new(self.statement());
+        let then_branch = Box::new(self.statement()?);
         let mut else_branch = None;
         if self.match_(&[TokenKind::Else]) {
-            else_branch = Some(Box::new(self.statement()));
+            else_branch = Some(Box::new(self.statement()?));
         }
-        Stmt::If {
+        Ok(Stmt::If {
             condition,
             then_branch,
             else_branch,
-        }
+        })
     }
 
-    fn block(&mut self) -> Vec<Stmt<'a>> {
+    fn block(&mut self) -> ParseResult<Vec<Stmt>> {
         let mut statements = Vec::new();
         while !self.check(&TokenKind::RightBrace) && !self.is_eof() {
-            statements.push(self.declaration());
+            if let Some(stmt) = self.declaration() {
+                statements.push(stmt);
+            }
         }
 
-        self.consume(TokenKind::RightBrace, "Expect '}' after block.")
-            .unwrap();
-        statements
+        self.consume(TokenKind::RightBrace, "Expect '}' after block.")?;
+        Ok(statements)
     }
 
-    fn print_statement(&mut self) -> Stmt<'a> {
-        let value = self.expression().unwrap();
-        self.consume(TokenKind::Semicolon, "Expect ';' after value.")
-            .unwrap();
-        Stmt::Print(value)
+    fn import_statement(&mut self) -> ParseResult<Stmt> {
+        let keyword = self.previous();
+        let path = self.consume(TokenKind::String, "Expect module path after 'import'.")?;
+        // `as` is only special here, so it stays usable as a name elsewhere
+        let mut alias = None;
+        if matches!(self.peek(), Some(t) if t.kind == TokenKind::Identifier && &*t.lexeme == "as") {
+            self.advance();
+            alias = Some(self.consume(TokenKind::Identifier, "Expect module name after 'as'.")?);
+        }
+        self.consume(TokenKind::Semicolon, "Expect ';' after module path.")?;
+        Ok(Stmt::Import {
+            keyword,
+            path,
+            alias,
+        })
     }
 
-    fn expression_statement(&mut self) -> Stmt<'a> {
-        let expr = self.expression().unwrap();
-        self.consume(TokenKind::Semicolon, "Expect ';' after expression")
-            .unwrap();
-        Stmt::Expression(expr)
+    fn throw_statement(&mut self) -> ParseResult<Stmt> {
+        let keyword = self.previous();
+        let value = self.expression()?;
+        self.consume(TokenKind::Semicolon, "Expect ';' after thrown value.")?;
+        Ok(Stmt::Throw { keyword, value })
     }
 
-    fn expression(&mut self) -> LoxResult<'a, Expr<'a>> {
+    fn try_statement(&mut self) -> ParseResult<Stmt> {
+        self.consume(TokenKind::LeftBrace, "Expect '{' after 'try'.")?;
+        let body = self.block()?;
+
+        let mut catch = None;
+        if self.match_(&[TokenKind::Catch]) {
+            self.consume(TokenKind::LeftParen, "Expect '(' after 'catch'.")?;
+            let name = self.consume(TokenKind::Identifier, "Expect caught variable name.")?;
+            self.consume(
+                TokenKind::RightParen,
+                "Expect ')' after caught variable name.",
+            )?;
+            self.consume(TokenKind::LeftBrace, "Expect '{' before catch body.")?;
+            catch = Some((name, self.block()?));
+        }
+
+        let mut finally = None;
+        if self.match_(&[TokenKind::Finally]) {
+            self.consume(TokenKind::LeftBrace, "Expect '{' after 'finally'.")?;
+            finally = Some(self.block()?);
+        }
+
+        if catch.is_none() && finally.is_none() {
+            return Err(ParserError {
+                token: self.peek().cloned().unwrap(),
+                message: "Expect 'catch' or 'finally' after try block.".to_string(),
+            });
+        }
+        Ok(Stmt::Try {
+            body,
+            catch,
+            finally,
+        })
+    }
+
+    fn print_statement(&mut self) -> ParseResult<Stmt> {
+        let keyword = self.previous();
+        let value = self.expression()?;
+        self.consume(TokenKind::Semicolon, "Expect ';' after value.")?;
+        Ok(Stmt::Print { keyword, value })
+    }
+
+    fn return_statement(&mut self) -> ParseResult<Stmt> {
+        let keyword = self.previous();
+        if self.function_depth == 0 {
+            self.error(keyword.clone(), "Can't return from top-level code.");
+        }
+
+        let mut value = None;
+        if !self.check(&TokenKind::Semicolon) {
+            value = Some(self.expression()?);
+        }
+        self.consume(TokenKind::Semicolon, "Expect ';' after return value.")?;
+
+        Ok(Stmt::Return { keyword, value })
+    }
+
+    fn expression_statement(&mut self) -> ParseResult<Stmt> {
+        let expr = self.expression()?;
+        self.consume(TokenKind::Semicolon, "Expect ';' after expression")?;
+        Ok(Stmt::Expression(expr))
+    }
+
+    fn expression(&mut self) -> ParseResult<Expr> {
         self.assignment()
     }
 
-    fn assignment(&mut self) -> LoxResult<'a, Expr<'a>> {
-        let expr = self.or()?;
+    fn assignment(&mut self) -> ParseResult<Expr> {
+        let expr = self.conditional()?;
 
         if self.match_(&[TokenKind::Equal]) {
             let equals = self.previous();
             let value = self.assignment()?;
 
             match expr {
-                Expr::Variable(name) => {
+                Expr::Variable { id, name } => {
                     return Ok(Expr::Assign {
+                        id,
+                        name,
+                        value: Box::new(value),
+                    })
+                }
+                Expr::Get { object, name } => {
+                    return Ok(Expr::Set {
+                        object,
                         name,
                         value: Box::new(value),
                     })
                 }
-                _ => eprintln!("{}. Invalid assignment target.", equals),
+                Expr::Index {
+                    object,
+                    bracket,
+                    index,
+                } => {
+                    return Ok(Expr::SetIndex {
+                        object,
+                        bracket,
+                        index,
+                        value: Box::new(value),
+                    })
+                }
+                _ => self.error(equals, "Invalid assignment target."),
             }
+        } else if self.match_(&[
+            TokenKind::PlusEqual,
+            TokenKind::MinusEqual,
+            TokenKind::StarEqual,
+            TokenKind::SlashEqual,
+        ]) {
+            let operator = self.previous();
+            let value = self.assignment()?;
+            self.check_assignment_target(&expr, &operator);
+            return Ok(Expr::CompoundAssign {
+                target: Box::new(expr),
+                operator: binary_operator(operator),
+                value: Box::new(value),
+            });
         }
 
         Ok(expr)
     }
 
-    fn or(&mut self) -> LoxResult<'a, Expr<'a>> {
+    // Compound assignments and postfix updates accept the same targets as `=`
+    fn check_assignment_target(&mut self, target: &Expr, operator: &Token) {
+        if !matches!(
+            target,
+            Expr::Variable { .. } | Expr::Get { .. } | Expr::Index { .. }
+        ) {
+            self.error(operator.clone(), "Invalid assignment target.");
+        }
+    }
+
+    // The else branch recurses so that a ? b : c ? d : e groups to the right
+    fn conditional(&mut self) -> ParseResult<Expr> {
+        let condition = self.or()?;
+        if self.match_(&[TokenKind::Question]) {
+            let then_branch = self.expression()?;
+            self.consume(
+                TokenKind::Colon,
+                "Expect ':' after then branch of conditional expression.",
+            )?;
+            let else_branch = self.conditional()?;
+            return Ok(Expr::Conditional {
+                condition: Box::new(condition),
+                then_branch: Box::new(then_branch),
+                else_branch: Box::new(else_branch),
+            });
+        }
+        Ok(condition)
+    }
+
+    fn or(&mut self) -> ParseResult<Expr> {
         let mut left = self.and()?;
         while self.match_(&[TokenKind::Or]) {
             let operator = self.previous();
@@ -249,7 +551,7 @@ impl<'a> Parser<'a> {
         Ok(left)
     }
 
-    fn and(&mut self) -> LoxResult<'a, Expr<'a>> {
+    fn and(&mut self) -> ParseResult<Expr> {
         let mut left = self.equality()?;
         while self.match_(&[TokenKind::And]) {
             let operator = self.previous();
@@ -263,7 +565,7 @@ impl<'a> Parser<'a> {
         Ok(left)
     }
 
-    fn comparison(&mut self) -> LoxResult<'a, Expr<'a>> {
+    fn comparison(&mut self) -> ParseResult<Expr> {
         let mut expr = self.term()?;
         while self.match_(&[
             TokenKind::Greater,
@@ -282,7 +584,7 @@ impl<'a> Parser<'a> {
         Ok(expr)
     }
 
-    fn term(&mut self) -> LoxResult<'a, Expr<'a>> {
+    fn term(&mut self) -> ParseResult<Expr> {
         let mut expr = self.factor()?;
         while self.match_(&[TokenKind::Minus, TokenKind::Plus]) {
             let operator = self.previous();
@@ -296,9 +598,14 @@ impl<'a> Parser<'a> {
         Ok(expr)
     }
 
-    fn factor(&mut self) -> LoxResult<'a, Expr<'a>> {
+    fn factor(&mut self) -> ParseResult<Expr> {
         let mut expr = self.unary()?;
-        while self.match_(&[TokenKind::Slash, TokenKind::Star]) {
+        while self.match_(&[
+            TokenKind::Slash,
+            TokenKind::Star,
+            TokenKind::Percent,
+            TokenKind::TildeSlash,
+        ]) {
             let operator = self.previous();
             let right = self.unary()?;
             expr = Expr::Binary {
@@ -310,7 +617,7 @@ impl<'a> Parser<'a> {
         Ok(expr)
     }
 
-    fn unary(&mut self) -> LoxResult<'a, Expr<'a>> {
+    fn unary(&mut self) -> ParseResult<Expr> {
         if self.match_(&[TokenKind::Bang, TokenKind::Minus]) {
             let operator = self.previous();
             let right = self.unary()?;
@@ -320,15 +627,59 @@ impl<'a> Parser<'a> {
             });
         }
 
-        self.call()
+        self.exponent()
+    }
+
+    // Binds tighter than unary minus on its left, so -2 ** 2 is -4, and
+    // recursing through unary on its right makes it right-associative
+    fn exponent(&mut self) -> ParseResult<Expr> {
+        let expr = self.postfix()?;
+        if self.match_(&[TokenKind::StarStar]) {
+            let operator = self.previous();
+            let right = self.unary()?;
+            return Ok(Expr::Binary {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+            });
+        }
+        Ok(expr)
     }
 
-    fn call(&mut self) -> LoxResult<'a, Expr<'a>> {
+    fn postfix(&mut self) -> ParseResult<Expr> {
+        let expr = self.call()?;
+        if self.match_(&[TokenKind::PlusPlus, TokenKind::MinusMinus]) {
+            let operator = self.previous();
+            self.check_assignment_target(&expr, &operator);
+            return Ok(Expr::Postfix {
+                target: Box::new(expr),
+                operator: binary_operator(operator),
+            });
+        }
+        Ok(expr)
+    }
+
+    fn call(&mut self) -> ParseResult<Expr> {
         let mut expr = self.primary()?;
 
         loop {
             if self.match_(&[TokenKind::LeftParen]) {
                 expr = self.finish_call(expr)?;
+            } else if self.match_(&[TokenKind::Dot]) {
+                let name =
+                    self.consume(TokenKind::Identifier, "Expect property name after '.'.")?;
+                expr = Expr::Get {
+                    object: Box::new(expr),
+                    name,
+                };
+            } else if self.match_(&[TokenKind::LeftBracket]) {
+                let index = self.expression()?;
+                let bracket = self.consume(TokenKind::RightBracket, "Expect ']' after index.")?;
+                expr = Expr::Index {
+                    object: Box::new(expr),
+                    bracket,
+                    index: Box::new(index),
+                };
             } else {
                 break;
             }
@@ -337,7 +688,7 @@ impl<'a> Parser<'a> {
         Ok(expr)
     }
 
-    fn finish_call(&mut self, callee: Expr<'a>) -> LoxResult<'a, Expr<'a>> {
+    fn finish_call(&mut self, callee: Expr) -> ParseResult<Expr> {
         let mut arguments = vec![];
         if !self.check(&TokenKind::RightParen) {
             arguments.push(Box::new(self.expression()?));
@@ -345,10 +696,8 @@ impl<'a> Parser<'a> {
                 arguments.push(Box::new(self.expression()?));
             }
             if arguments.len() >= 255 {
-                eprintln!(
-                    "{}, Can't have more than 255 arguments.",
-                    self.peek().unwrap()
-                );
+                let token = self.peek().cloned().unwrap();
+                self.error(token, "Can't have more than 255 arguments.");
             }
         }
 
@@ -361,7 +710,7 @@ impl<'a> Parser<'a> {
         })
     }
 
-    fn primary(&mut self) -> LoxResult<'a, Expr<'a>> {
+    fn primary(&mut self) -> ParseResult<Expr> {
         if self.match_(&[
             TokenKind::False,
             TokenKind::True,
@@ -373,8 +722,51 @@ impl<'a> Parser<'a> {
             return Ok(expr);
         }
 
+        if self.match_(&[TokenKind::Interpolation]) {
+            let mut parts = vec![];
+            loop {
+                parts.push(Box::new(Expr::Literal(self.previous().literal.unwrap())));
+                parts.push(Box::new(self.expression()?));
+                if !self.match_(&[TokenKind::Interpolation]) {
+                    break;
+                }
+            }
+            let end = self.consume(
+                TokenKind::String,
+                "Expect '}' after interpolated expression.",
+            )?;
+            parts.push(Box::new(Expr::Literal(end.literal.unwrap())));
+            return Ok(Expr::Interpolation(parts));
+        }
+
+        if self.match_(&[TokenKind::Super]) {
+            let keyword = self.previous();
+            self.consume(TokenKind::Dot, "Expect '.' after 'super'.")?;
+            let method = self.consume(TokenKind::Identifier, "Expect superclass method name.")?;
+            return Ok(Expr::Super {
+                id: next_expr_id(),
+                keyword,
+                method,
+            });
+        }
+
+        if self.match_(&[TokenKind::Fun]) {
+            let keyword = self.previous();
+            return Ok(Expr::Lambda(self.function_body("lambda", keyword)?));
+        }
+
+        if self.match_(&[TokenKind::This]) {
+            return Ok(Expr::This {
+                id: next_expr_id(),
+                keyword: self.previous(),
+            });
+        }
+
         if self.match_(&[TokenKind::Identifier]) {
-            return Ok(Expr::Variable(self.previous()));
+            return Ok(Expr::Variable {
+                id: next_expr_id(),
+                name: self.previous(),
+            });
         }
 
         if self.match_(&[TokenKind::LeftParen]) {
@@ -383,24 +775,63 @@ impl<'a> Parser<'a> {
             return Ok(Expr::Grouping(Box::new(expr)));
         }
 
-        Err(Error::Syntactic(ParserError {
+        if self.match_(&[TokenKind::LeftBracket]) {
+            let mut elements = vec![];
+            if !self.check(&TokenKind::RightBracket) {
+                elements.push(Box::new(self.expression()?));
+                while self.match_(&[TokenKind::Comma]) {
+                    elements.push(Box::new(self.expression()?));
+                }
+            }
+            self.consume(TokenKind::RightBracket, "Expect ']' after list elements.")?;
+            return Ok(Expr::List(elements));
+        }
+
+        if self.match_(&[TokenKind::LeftBrace]) {
+            let brace = self.previous();
+            let mut entries = vec![];
+            if !self.check(&TokenKind::RightBrace) {
+                loop {
+                    let key = self.expression()?;
+                    self.consume(TokenKind::Colon, "Expect ':' after map key.")?;
+                    let value = self.expression()?;
+                    entries.push((Box::new(key), Box::new(value)));
+                    if !self.match_(&[TokenKind::Comma]) {
+                        break;
+                    }
+                }
+            }
+            self.consume(TokenKind::RightBrace, "Expect '}' after map entries.")?;
+            return Ok(Expr::Map { brace, entries });
+        }
+
+        Err(ParserError {
             token: self.peek().cloned().unwrap(),
-            message: "Expected expression".to_string(),
-        }))
+            message: "Expect expression.".to_string(),
+        })
     }
 
-    fn consume(&mut self, token_kind: TokenKind, message: &str) -> LoxResult<'a, Token<'a>> {
+    fn consume(&mut self, token_kind: TokenKind, message: &str) -> ParseResult<Token> {
         if self.check(&token_kind) {
             return Ok(self.advance());
         }
 
-        Err(Error::Syntactic(ParserError {
+        Err(ParserError {
             token: self.tokens.get(self.current).cloned().unwrap(),
             message: String::from(message),
-        }))
+        })
     }
 
-    fn previous(&mut self) -> Token<'a> {
+    // Records an error that doesn't leave the parser confused,
+    // so there is no need to synchronize
+    fn error(&mut self, token: Token, message: &str) {
+        self.errors.push(ParserError {
+            token,
+            message: String::from(message),
+        });
+    }
+
+    fn previous(&mut self) -> Token {
         self.tokens.get(self.current - 1).cloned().unwrap()
     }
 
@@ -408,11 +839,11 @@ impl<'a> Parser<'a> {
         matches!(self.peek(), Some(t) if t.kind == TokenKind::Eof)
     }
 
-    fn peek(&self) -> Option<&Token<'a>> {
+    fn peek(&self) -> Option<&Token> {
         self.tokens.get(self.current)
     }
 
-    fn equality(&mut self) -> LoxResult<'a, Expr<'a>> {
+    fn equality(&mut self) -> ParseResult<Expr> {
         let mut expr = self.comparison()?;
         while self.match_(&[TokenKind::BangEqual, TokenKind::EqualEqual]) {
             let operator = self.previous();
@@ -443,7 +874,12 @@ impl<'a> Parser<'a> {
         matches!(self.peek(), Some(t) if t.kind == *token_kind)
     }
 
-    fn advance(&mut self) -> Token<'a> {
+    // Like `check`, but one token further ahead
+    fn check_next(&self, token_kind: &TokenKind) -> bool {
+        matches!(self.tokens.get(self.current + 1), Some(t) if t.kind == *token_kind)
+    }
+
+    fn advance(&mut self) -> Token {
         if !self.is_eof() {
             self.current += 1;
         }
@@ -463,11 +899,15 @@ impl<'a> Parser<'a> {
                     TokenKind::Class
                     | TokenKind::Fun
                     | TokenKind::Var
+                    | TokenKind::Const
                     | TokenKind::For
                     | TokenKind::If
                     | TokenKind::While
                     | TokenKind::Print
-                    | TokenKind::Return => return,
+                    | TokenKind::Return
+                    | TokenKind::Throw
+                    | TokenKind::Try
+                    | TokenKind::Import => return,
                     _ => (),
                 }
             }
@@ -476,3 +916,20 @@ impl<'a> Parser<'a> {
         }
     }
 }
+
+// The token of a compound assignment or postfix update, retagged as the
+// binary operator it applies. The lexeme stays, so errors still show `+=`.
+fn binary_operator(operator: Token) -> Token {
+    let kind = match operator.kind {
+        TokenKind::PlusEqual | TokenKind::PlusPlus => TokenKind::Plus,
+        TokenKind::MinusEqual | TokenKind::MinusMinus => TokenKind::Minus,
+        TokenKind::StarEqual => TokenKind::Star,
+        TokenKind::SlashEqual => TokenKind::Slash,
+        kind => kind,
+    };
+    Token { kind, ..operator }
+}
+
+fn next_expr_id() -> ExprId {
+    NEXT_EXPR_ID.fetch_add(1, Ordering::Relaxed)
+}
@@ -0,0 +1,469 @@
+use crate::error::{Error, LoxResult, ParserError};
+use crate::expr::{Expr, ExprId};
+use crate::interpreter::Interpreter;
+use crate::stmt::{FunctionDecl, Stmt};
+use crate::token::Token;
+use crate::visitor::Visitor;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+#[derive(Copy, Clone, PartialEq)]
+enum FunctionType {
+    None,
+    Function,
+    Initializer,
+    Method,
+}
+
+#[derive(Copy, Clone)]
+struct Variable {
+    // Whether the initializer is done, so the variable is ready for use
+    defined: bool,
+    constant: bool,
+}
+
+impl Variable {
+    // Defined and reassignable, like every variable but constants
+    const READY: Variable = Variable {
+        defined: true,
+        constant: false,
+    };
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum ClassType {
+    None,
+    Class,
+    Subclass,
+}
+
+// Walks the AST once before it is interpreted and tells the interpreter
+// how many environments away each local variable is bound.
+pub struct Resolver<'i> {
+    interpreter: &'i mut Interpreter,
+    // Innermost scope last. Globals are not tracked, apart from
+    // which ones are constants.
+    scopes: Vec<HashMap<Rc<str>, Variable>>,
+    global_constants: HashSet<Rc<str>>,
+    current_function: FunctionType,
+    current_class: ClassType,
+}
+
+impl<'i> Visitor<LoxResult<()>, LoxResult<()>> for Resolver<'i> {
+    fn visit_expr(&mut self, expr: &Expr) -> LoxResult<()> {
+        match expr {
+            Expr::Variable { id, name } => {
+                if let Some(scope) = self.scopes.last() {
+                    if scope
+                        .get(&name.lexeme)
+                        .is_some_and(|variable| !variable.defined)
+                    {
+                        return Err(error(
+                            name,
+                            "Can't read local variable in its own initializer.",
+                        ));
+                    }
+                }
+                self.resolve_local(*id, name);
+            }
+            Expr::Assign { id, name, value } => {
+                self.visit_expr(value)?;
+                self.check_assignable(name)?;
+                self.resolve_local(*id, name);
+            }
+            Expr::Binary { left, right, .. } | Expr::Logical { left, right, .. } => {
+                self.visit_expr(left)?;
+                self.visit_expr(right)?;
+            }
+            Expr::Call {
+                callee, arguments, ..
+            } => {
+                self.visit_expr(callee)?;
+                for argument in arguments {
+                    self.visit_expr(argument)?;
+                }
+            }
+            Expr::CompoundAssign { target, value, .. } => {
+                self.visit_expr(value)?;
+                if let Expr::Variable { name, .. } = target.as_ref() {
+                    self.check_assignable(name)?;
+                }
+                self.visit_expr(target)?;
+            }
+            Expr::Postfix { target, .. } => {
+                if let Expr::Variable { name, .. } = target.as_ref() {
+                    self.check_assignable(name)?;
+                }
+                self.visit_expr(target)?;
+            }
+            Expr::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.visit_expr(condition)?;
+                self.visit_expr(then_branch)?;
+                self.visit_expr(else_branch)?;
+            }
+            Expr::Get { object, .. } => self.visit_expr(object)?,
+            Expr::Set { object, value, .. } => {
+                self.visit_expr(value)?;
+                self.visit_expr(object)?;
+            }
+            Expr::Grouping(expr) => self.visit_expr(expr)?,
+            Expr::Index { object, index, .. } => {
+                self.visit_expr(object)?;
+                self.visit_expr(index)?;
+            }
+            Expr::SetIndex {
+                object,
+                index,
+                value,
+                ..
+            } => {
+                self.visit_expr(value)?;
+                self.visit_expr(object)?;
+                self.visit_expr(index)?;
+            }
+            Expr::Map { entries, .. } => {
+                for (key, value) in entries {
+                    self.visit_expr(key)?;
+                    self.visit_expr(value)?;
+                }
+            }
+            Expr::Interpolation(elements) | Expr::List(elements) => {
+                for element in elements {
+                    self.visit_expr(element)?;
+                }
+            }
+            Expr::Lambda(declaration) => {
+                self.resolve_function(declaration, FunctionType::Function)?
+            }
+            Expr::Unary { right, .. } => self.visit_expr(right)?,
+            Expr::This { id, keyword } => {
+                if self.current_class == ClassType::None {
+                    return Err(error(keyword, "Can't use 'this' outside of a class."));
+                }
+                self.resolve_local(*id, keyword);
+            }
+            Expr::Super { id, keyword, .. } => {
+                match self.current_class {
+                    ClassType::None => {
+                        return Err(error(keyword, "Can't use 'super' outside of a class."))
+                    }
+                    ClassType::Class => {
+                        return Err(error(
+                            keyword,
+                            "Can't use 'super' in a class with no superclass.",
+                        ))
+                    }
+                    ClassType::Subclass => (),
+                }
+                self.resolve_local(*id, keyword);
+            }
+            Expr::Literal(_) => (),
+        }
+        Ok(())
+    }
+
+    fn visit_stmt(&mut self, stmt: &Stmt) -> LoxResult<()> {
+        match stmt {
+            Stmt::Block(statements) => self.resolve_block(statements)?,
+            Stmt::Var(name, initializer) => {
+                self.declare(name)?;
+                self.visit_expr(initializer)?;
+                self.define(name);
+            }
+            Stmt::Const(name, initializer) => {
+                self.declare(name)?;
+                self.visit_expr(initializer)?;
+                self.define_constant(name);
+            }
+            Stmt::Function(declaration) => {
+                self.declare(&declaration.name)?;
+                self.define(&declaration.name);
+                self.resolve_function(declaration, FunctionType::Function)?;
+            }
+            Stmt::Class {
+                name,
+                superclass,
+                methods,
+            } => {
+                let enclosing_class = self.current_class;
+                self.current_class = ClassType::Class;
+
+                self.declare(name)?;
+                self.define(name);
+
+                if let Some(superclass) = superclass {
+                    if let Expr::Variable {
+                        name: superclass_name,
+                        ..
+                    } = superclass
+                    {
+                        if superclass_name.lexeme == name.lexeme {
+                            return Err(error(
+                                superclass_name,
+                                "A class can't inherit from itself.",
+                            ));
+                        }
+                    }
+                    self.current_class = ClassType::Subclass;
+                    self.visit_expr(superclass)?;
+
+                    self.begin_scope();
+                    if let Some(scope) = self.scopes.last_mut() {
+                        scope.insert("super".into(), Variable::READY);
+                    }
+                }
+
+                self.begin_scope();
+                if let Some(scope) = self.scopes.last_mut() {
+                    scope.insert("this".into(), Variable::READY);
+                }
+                let result = methods.iter().try_for_each(|method| {
+                    let kind = if &*method.name.lexeme == "init" {
+                        FunctionType::Initializer
+                    } else {
+                        FunctionType::Method
+                    };
+                    self.resolve_function(method, kind)
+                });
+                self.end_scope();
+                if superclass.is_some() {
+                    self.end_scope();
+                }
+
+                self.current_class = enclosing_class;
+                result?;
+            }
+            Stmt::Expression(expr) | Stmt::Print { value: expr, .. } => self.visit_expr(expr)?,
+            Stmt::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.visit_expr(condition)?;
+                self.visit_stmt(then_branch)?;
+                if let Some(else_branch) = else_branch {
+                    self.visit_stmt(else_branch)?;
+                }
+            }
+            Stmt::While {
+                condition,
+                body,
+                increment,
+            } => {
+                self.visit_expr(condition)?;
+                self.visit_stmt(body)?;
+                if let Some(increment) = increment {
+                    self.visit_expr(increment)?;
+                }
+            }
+            Stmt::ForIn {
+                name,
+                iterable,
+                body,
+                ..
+            } => {
+                self.visit_expr(iterable)?;
+                self.begin_scope();
+                let result = self.declare(name).and_then(|_| {
+                    self.define(name);
+                    self.visit_stmt(body)
+                });
+                self.end_scope();
+                result?;
+            }
+            Stmt::Break(_) | Stmt::Continue(_) => (),
+            Stmt::Throw { value, .. } => self.visit_expr(value)?,
+            // The module is bound as a global, so it doesn't need resolving
+            Stmt::Import { keyword, .. } => {
+                if !self.scopes.is_empty() {
+                    return Err(error(keyword, "Can only import at the top level."));
+                }
+            }
+            Stmt::Try {
+                body,
+                catch,
+                finally,
+            } => {
+                self.resolve_block(body)?;
+                if let Some((name, handler)) = catch {
+                    // The caught value lives in the same scope as the handler
+                    self.begin_scope();
+                    let result = self.declare(name).and_then(|_| {
+                        self.define(name);
+                        self.resolve(handler)
+                    });
+                    self.end_scope();
+                    result?;
+                }
+                if let Some(finally) = finally {
+                    self.resolve_block(finally)?;
+                }
+            }
+            Stmt::Return { keyword, value } => {
+                if let Some(value) = value {
+                    if self.current_function == FunctionType::Initializer {
+                        return Err(error(keyword, "Can't return a value from an initializer."));
+                    }
+                    self.visit_expr(value)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'i> Resolver<'i> {
+    pub fn new(interpreter: &'i mut Interpreter) -> Self {
+        Self {
+            interpreter,
+            scopes: Vec::new(),
+            global_constants: HashSet::new(),
+            current_function: FunctionType::None,
+            current_class: ClassType::None,
+        }
+    }
+
+    pub fn resolve(&mut self, statements: &[Stmt]) -> LoxResult<()> {
+        for statement in statements {
+            self.visit_stmt(statement)?;
+        }
+        Ok(())
+    }
+
+    fn resolve_block(&mut self, statements: &[Stmt]) -> LoxResult<()> {
+        self.begin_scope();
+        let result = self.resolve(statements);
+        self.end_scope();
+        result
+    }
+
+    fn resolve_function(
+        &mut self,
+        declaration: &FunctionDecl,
+        kind: FunctionType,
+    ) -> LoxResult<()> {
+        let enclosing_function = self.current_function;
+        self.current_function = kind;
+        self.begin_scope();
+        let result = declaration
+            .params
+            .iter()
+            .try_for_each(|param| {
+                self.declare(&param.name)?;
+                if let Some(default) = &param.default {
+                    self.visit_expr(default)?;
+                }
+                self.define(&param.name);
+                Ok(())
+            })
+            .and_then(|_| match &declaration.rest {
+                Some(rest) => {
+                    self.declare(rest)?;
+                    self.define(rest);
+                    Ok(())
+                }
+                None => Ok(()),
+            })
+            .and_then(|_| self.resolve(&declaration.body));
+        self.end_scope();
+        self.current_function = enclosing_function;
+        result
+    }
+
+    fn resolve_local(&mut self, id: ExprId, name: &Token) {
+        for (depth, scope) in self.scopes.iter().rev().enumerate() {
+            if scope.contains_key(&name.lexeme) {
+                self.interpreter.resolve(id, depth);
+                return;
+            }
+        }
+    }
+
+    fn begin_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    fn end_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn declare(&mut self, name: &Token) -> LoxResult<()> {
+        if self.scopes.is_empty() && self.global_constants.contains(&name.lexeme) {
+            return Err(error(
+                name,
+                &format!("Can't redeclare constant '{}'.", name.lexeme),
+            ));
+        }
+        if let Some(scope) = self.scopes.last_mut() {
+            if scope.contains_key(&name.lexeme) {
+                return Err(error(
+                    name,
+                    "Already a variable with this name in this scope.",
+                ));
+            }
+            scope.insert(
+                name.lexeme.clone(),
+                Variable {
+                    defined: false,
+                    constant: false,
+                },
+            );
+        }
+        Ok(())
+    }
+
+    fn define(&mut self, name: &Token) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.lexeme.clone(), Variable::READY);
+        }
+    }
+
+    fn define_constant(&mut self, name: &Token) {
+        match self.scopes.last_mut() {
+            Some(scope) => {
+                scope.insert(
+                    name.lexeme.clone(),
+                    Variable {
+                        defined: true,
+                        constant: true,
+                    },
+                );
+            }
+            None => {
+                self.global_constants.insert(name.lexeme.clone());
+            }
+        }
+    }
+
+    // Finds the variable an assignment to `name` would change. Global
+    // constants declared later than the assignment are left to the interpreter.
+    fn check_assignable(&self, name: &Token) -> LoxResult<()> {
+        let constant = match self
+            .scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(&name.lexeme))
+        {
+            Some(variable) => variable.constant,
+            None => self.global_constants.contains(&name.lexeme),
+        };
+        if constant {
+            return Err(error(
+                name,
+                &format!("Can't assign to constant '{}'.", name.lexeme),
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn error(token: &Token, message: &str) -> Error {
+    Error::Semantic(ParserError {
+        token: token.clone(),
+        message: message.to_string(),
+    })
+}
@@ -1,24 +1,85 @@
 use crate::expr::Expr;
 use crate::token::Token;
+use std::rc::Rc;
 
 #[derive(Clone, PartialEq, Debug)]
-pub enum Stmt<'a> {
-    Print(Expr<'a>),
-    Expression(Expr<'a>),
-    Var(Token<'a>, Expr<'a>),
-    Block(Vec<Stmt<'a>>),
+pub enum Stmt {
+    Print {
+        keyword: Token,
+        value: Expr,
+    },
+    Expression(Expr),
+    Var(Token, Expr),
+    Const(Token, Expr),
+    Block(Vec<Stmt>),
     If {
-        condition: Expr<'a>,
-        then_branch: Box<Stmt<'a>>,
-        else_branch: Option<Box<Stmt<'a>>>,
+        condition: Expr,
+        then_branch: Box<Stmt>,
+        else_branch: Option<Box<Stmt>>,
     },
     While {
-        condition: Box<Expr<'a>>,
-        body: Box<Stmt<'a>>,
+        condition: Box<Expr>,
+        body: Box<Stmt>,
+        // Only set for desugared `for` loops, runs after every
+        // iteration of the body, including ones cut short by `continue`
+        increment: Option<Box<Expr>>,
+    },
+    // `for (var name in iterable) body`, with a fresh `name` every iteration
+    ForIn {
+        name: Token,
+        keyword: Token,
+        iterable: Expr,
+        body: Box<Stmt>,
+    },
+    Break(Token),
+    Continue(Token),
+    Function(Rc<FunctionDecl>),
+    Return {
+        keyword: Token,
+        value: Option<Expr>,
+    },
+    Throw {
+        keyword: Token,
+        value: Expr,
+    },
+    // At least one of `catch` and `finally` is present
+    Try {
+        body: Vec<Stmt>,
+        // The name the caught value is bound to and the handler
+        catch: Option<(Token, Vec<Stmt>)>,
+        finally: Option<Vec<Stmt>>,
+    },
+    // Binds the module to `alias` if given, otherwise to the file name of
+    // `path` without the extension
+    Import {
+        keyword: Token,
+        path: Token,
+        alias: Option<Token>,
     },
-    Function {
-        name: Token<'a>,
-        params: Vec<Token<'a>>,
-        body: Vec<Stmt<'a>>,
+    Class {
+        name: Token,
+        superclass: Option<Expr>,
+        methods: Vec<Rc<FunctionDecl>>,
     },
 }
+
+// Shared between the AST and every `LoxFunction` created from it,
+// so declaring a function doesn't copy its body.
+#[derive(Clone, PartialEq, Debug)]
+pub struct FunctionDecl {
+    // The `fun` keyword for lambdas, which have no name
+    pub name: Token,
+    // Parameters with a default value come after the ones without
+    pub params: Vec<Param>,
+    // Bound to a list of the arguments after the other parameters
+    pub rest: Option<Token>,
+    pub body: Vec<Stmt>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Param {
+    pub name: Token,
+    // Evaluated at every call that leaves the parameter out,
+    // and can refer to the parameters before it
+    pub default: Option<Expr>,
+}
@@ -1,6 +1,13 @@
 use crate::lox_callable::LoxCallable;
+use crate::lox_class::LoxClass;
+use crate::lox_instance::LoxInstance;
+use crate::lox_module::LoxModule;
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
 use std::fmt;
 use std::fmt::{Display, Formatter};
+use std::rc::Rc;
 
 macro_rules! enum_str {
     (
@@ -32,10 +39,16 @@ enum_str! {
         RightParen,
         LeftBrace,
         RightBrace,
+        LeftBracket,
+        RightBracket,
+        Colon,
         Comma,
         Dot,
+        Ellipsis,
         Minus,
+        Percent,
         Plus,
+        Question,
         Semicolon,
         Slash,
         Star,
@@ -49,27 +62,46 @@ enum_str! {
         GreaterEqual,
         Less,
         LessEqual,
+        MinusEqual,
+        MinusMinus,
+        PlusEqual,
+        PlusPlus,
+        SlashEqual,
+        StarEqual,
+        StarStar,
+        TildeSlash,
 
         // Literals
         Identifier,
         String,
+        // The part of a string literal before a `${`
+        Interpolation,
         Number,
 
         // Keywords
         And,
+        Break,
+        Catch,
         Class,
+        Const,
+        Continue,
         Else,
         False,
+        Finally,
         Fun,
         For,
         If,
+        Import,
+        In,
         Nil,
         Or,
         Print,
         Return,
         Super,
         This,
+        Throw,
         True,
+        Try,
         Var,
         While,
 
@@ -79,41 +111,195 @@ enum_str! {
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug)]
 pub enum Literal {
     Number(f64),
     String(String),
     Bool(bool),
     Nil,
-    Callable(Box<dyn LoxCallable>),
+    Callable(Rc<dyn LoxCallable>),
+    Class(Rc<LoxClass>),
+    Instance(Rc<RefCell<LoxInstance>>),
+    List(Rc<RefCell<Vec<Literal>>>),
+    Map(Rc<RefCell<BTreeMap<MapKey, Literal>>>),
+    Module(Rc<LoxModule>),
+    Range(Range),
+}
+
+// The numbers from `start` up to, but not including, `end`, `step` apart.
+// A negative step counts down. Made by the `range` native.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Range {
+    pub start: f64,
+    pub end: f64,
+    pub step: f64,
+}
+
+impl Range {
+    pub fn iter(&self) -> impl Iterator<Item = f64> {
+        let Range { start, end, step } = *self;
+        // Multiplying instead of adding up steps keeps rounding errors from piling up
+        (0u64..)
+            .map(move |i| start + i as f64 * step)
+            .take_while(move |n| if step > 0.0 { *n < end } else { *n > end })
+    }
+}
+
+// A value that can be used as a map key. Only strings and numbers
+// qualify; a map keeps its keys sorted, numbers before strings.
+#[derive(Clone, Debug)]
+pub enum MapKey {
+    Number(f64),
+    String(String),
+}
+
+impl MapKey {
+    // NaN can't be a key since it is not equal to itself
+    pub fn from_literal(literal: &Literal) -> Option<MapKey> {
+        match literal {
+            // -0 and 0 are equal in Lox, so they must be the same key
+            Literal::Number(n) if !n.is_nan() => Some(MapKey::Number(n + 0.0)),
+            Literal::String(s) => Some(MapKey::String(s.clone())),
+            _ => None,
+        }
+    }
+
+    pub fn to_literal(&self) -> Literal {
+        match self {
+            MapKey::Number(n) => Literal::Number(*n),
+            MapKey::String(s) => Literal::String(s.clone()),
+        }
+    }
+}
+
+impl Ord for MapKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Number(n1), Self::Number(n2)) => n1.total_cmp(n2),
+            (Self::String(s1), Self::String(s2)) => s1.cmp(s2),
+            (Self::Number(_), Self::String(_)) => Ordering::Less,
+            (Self::String(_), Self::Number(_)) => Ordering::Greater,
+        }
+    }
+}
+
+impl PartialOrd for MapKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for MapKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for MapKey {}
+
+impl Display for MapKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_literal())
+    }
+}
+
+// Lox equality: values of different types are never equal, so nil only
+// equals nil and there is no coercion between numbers and strings.
+// Numbers follow IEEE 754, which means NaN is not equal to itself and
+// 0 equals -0. Functions, classes, instances, lists, maps and modules
+// are equal only to themselves. Ranges are equal when their start, end
+// and step are, even if two different ones hold the same numbers.
+impl PartialEq for Literal {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Number(n1), Self::Number(n2)) => n1 == n2,
+            (Self::String(s1), Self::String(s2)) => s1 == s2,
+            (Self::Bool(b1), Self::Bool(b2)) => b1 == b2,
+            (Self::Nil, Self::Nil) => true,
+            (Self::Callable(c1), Self::Callable(c2)) => Rc::ptr_eq(c1, c2),
+            (Self::Class(c1), Self::Class(c2)) => Rc::ptr_eq(c1, c2),
+            (Self::Instance(i1), Self::Instance(i2)) => Rc::ptr_eq(i1, i2),
+            (Self::List(l1), Self::List(l2)) => Rc::ptr_eq(l1, l2),
+            (Self::Map(m1), Self::Map(m2)) => Rc::ptr_eq(m1, m2),
+            (Self::Module(m1), Self::Module(m2)) => Rc::ptr_eq(m1, m2),
+            (Self::Range(r1), Self::Range(r2)) => r1 == r2,
+            _ => false,
+        }
+    }
 }
 
 impl Display for Literal {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.write_nested(f, &mut Vec::new())
+    }
+}
+
+impl Literal {
+    // Lists and maps can contain themselves, so `enclosing` holds the ones
+    // currently being written and one reached again is written as `[...]`
+    // or `{...}`.
+    fn write_nested(&self, f: &mut Formatter<'_>, enclosing: &mut Vec<*const ()>) -> fmt::Result {
         match self {
             Self::Number(num) => write!(f, "{}", num),
             Self::String(s) => write!(f, "{}", s),
             Self::Bool(b) => write!(f, "{}", b),
             Self::Nil => write!(f, "nil"),
-            Self::Callable(c) => write!(f, "function"),
+            Self::Callable(c) => write!(f, "{}", c),
+            Self::Class(c) => write!(f, "{}", c),
+            Self::Instance(i) => write!(f, "{}", i.borrow()),
+            Self::List(list) => {
+                let pointer = Rc::as_ptr(list) as *const ();
+                if enclosing.contains(&pointer) {
+                    return write!(f, "[...]");
+                }
+                enclosing.push(pointer);
+                write!(f, "[")?;
+                for (i, element) in list.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    element.write_nested(f, enclosing)?;
+                }
+                enclosing.pop();
+                write!(f, "]")
+            }
+            Self::Map(map) => {
+                let pointer = Rc::as_ptr(map) as *const ();
+                if enclosing.contains(&pointer) {
+                    return write!(f, "{{...}}");
+                }
+                enclosing.push(pointer);
+                write!(f, "{{")?;
+                for (i, (key, value)) in map.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: ", key)?;
+                    value.write_nested(f, enclosing)?;
+                }
+                enclosing.pop();
+                write!(f, "}}")
+            }
+            Self::Module(m) => write!(f, "{}", m),
+            Self::Range(r) => write!(f, "range({}, {}, {})", r.start, r.end, r.step),
         }
     }
 }
 
 #[derive(Clone, Debug, PartialEq)]
-pub struct Token<'a> {
+pub struct Token {
     pub kind: TokenKind,
-    pub lexeme: &'a str,
+    pub lexeme: Rc<str>,
     pub literal: Option<Literal>,
     pub start: usize,
     pub line: usize,
 }
 
-impl<'a> Token<'a> {
-    pub fn new(kind: TokenKind, lexeme: &'a str, start: usize, line: usize) -> Self {
+impl Token {
+    pub fn new(kind: TokenKind, lexeme: &str, start: usize, line: usize) -> Self {
         Self {
             kind,
-            lexeme,
+            lexeme: lexeme.into(),
             literal: None,
             start,
             line,
@@ -122,14 +308,14 @@ impl<'a> Token<'a> {
 
     pub fn new_full(
         kind: TokenKind,
-        lexeme: &'a str,
+        lexeme: &str,
         literal: Literal,
         start: usize,
         line: usize,
     ) -> Self {
         Self {
             kind,
-            lexeme,
+            lexeme: lexeme.into(),
             literal: Some(literal),
             start,
             line,
@@ -137,7 +323,7 @@ impl<'a> Token<'a> {
     }
 }
 
-impl<'a> fmt::Display for Token<'a> {
+impl fmt::Display for Token {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
@@ -150,3 +336,35 @@ impl<'a> fmt::Display for Token<'a> {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::token::{Literal, Range};
+
+    #[test]
+    fn nil_only_equals_nil() {
+        assert_eq!(Literal::Nil, Literal::Nil);
+        assert_ne!(Literal::Nil, Literal::Bool(false));
+        assert_ne!(Literal::Nil, Literal::Number(0.0));
+    }
+
+    #[test]
+    fn no_coercion_between_types() {
+        assert_ne!(Literal::Number(1.0), Literal::String("1".to_string()));
+        assert_ne!(Literal::Bool(true), Literal::Number(1.0));
+    }
+
+    #[test]
+    fn numbers_follow_ieee_754() {
+        assert_ne!(Literal::Number(f64::NAN), Literal::Number(f64::NAN));
+        assert_eq!(Literal::Number(0.0), Literal::Number(-0.0));
+    }
+
+    #[test]
+    fn ranges_compare_start_end_and_step() {
+        let range = |start, end, step| Literal::Range(Range { start, end, step });
+        assert_eq!(range(0.0, 5.0, 1.0), range(0.0, 5.0, 1.0));
+        assert_ne!(range(0.0, 5.0, 1.0), range(0.0, 5.0, 2.0));
+        assert_ne!(range(0.0, 0.0, 1.0), range(5.0, 5.0, 1.0));
+    }
+}
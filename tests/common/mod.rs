@@ -0,0 +1,34 @@
+use rlox::lox::Lox;
+use std::cell::RefCell;
+use std::io;
+use std::io::Write;
+use std::rc::Rc;
+
+// Collects everything a script prints, shared with the test reading it
+#[derive(Clone, Default)]
+struct Output(Rc<RefCell<Vec<u8>>>);
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// Runs the script at `script_path`, which must succeed, and returns the
+// lines it printed
+pub fn run_file(script_path: &str) -> Vec<String> {
+    let output = Output::default();
+    Lox::with_output(output.clone())
+        .run_file(script_path)
+        .unwrap();
+    let bytes = output.0.borrow();
+    String::from_utf8_lossy(&bytes)
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
@@ -0,0 +1,13 @@
+mod common;
+
+#[test]
+fn test_arithmetic() {
+    let script_path = "./tests/data/arithmetic.rlox";
+    assert_eq!(
+        common::run_file(script_path),
+        [
+            "1", "2", "1.5", "3", "-4", "1024", "512", "-4", "0.5", "3", "10", "-2", "-1", "0.5",
+            "-7", "true", "false"
+        ]
+    );
+}
@@ -1,5 +1,13 @@
+mod common;
+
 #[test]
 fn test_blocks() {
     let script_path = "./tests/data/blocks.rlox";
-    rlox::lox::Lox::new().run_file(script_path).unwrap();
+    assert_eq!(
+        common::run_file(script_path),
+        [
+            "inner a", "outer b", "global c", "outer a", "outer b", "global c", "global a",
+            "global b", "global c",
+        ]
+    );
 }
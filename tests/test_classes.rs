@@ -0,0 +1,10 @@
+mod common;
+
+#[test]
+fn test_classes() {
+    let script_path = "./tests/data/classes.rlox";
+    assert_eq!(
+        common::run_file(script_path),
+        ["4", "6", "Point instance", "Point", "3", "0", "boxed"]
+    );
+}
@@ -0,0 +1,10 @@
+mod common;
+
+#[test]
+fn test_closures() {
+    let script_path = "./tests/data/closures.rlox";
+    assert_eq!(
+        common::run_file(script_path),
+        ["1", "2", "1", "3", "15", "hello"]
+    );
+}
@@ -0,0 +1,10 @@
+mod common;
+
+#[test]
+fn test_comments() {
+    let script_path = "./tests/data/comments.rlox";
+    assert_eq!(
+        common::run_file(script_path),
+        ["one", "two", "3", "3", "three"]
+    );
+}
@@ -0,0 +1,34 @@
+mod common;
+
+#[test]
+fn test_compound_assignment() {
+    let script_path = "./tests/data/compound_assignment.rlox";
+    assert_eq!(
+        common::run_file(script_path),
+        [
+            "15",
+            "12",
+            "24",
+            "6",
+            "abcd",
+            "0",
+            "1",
+            "1",
+            "0",
+            "0",
+            "1",
+            "2",
+            "11",
+            "[101, 2, 2]",
+            "5",
+            "{hits: 6}",
+            "[101, 4, 2]",
+            "4",
+            "2",
+            "2",
+            "[2]",
+            "1",
+            "List index 1 out of bounds for length 1.",
+        ]
+    );
+}
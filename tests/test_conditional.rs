@@ -0,0 +1,13 @@
+mod common;
+
+#[test]
+fn test_conditional() {
+    let script_path = "./tests/data/conditional.rlox";
+    assert_eq!(
+        common::run_file(script_path),
+        [
+            "apple", "apples", "positive", "negative", "zero", "then", "0", "else", "0", "2", "2",
+            "yes",
+        ]
+    );
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use rlox::error::Error;
+use rlox::lox::Lox;
+
+#[test]
+fn test_constants() {
+    let script_path = "./tests/data/constants.rlox";
+    assert_eq!(
+        common::run_file(script_path),
+        ["hello", "20", "2", "reassigned", "12"]
+    );
+}
+
+#[test]
+fn test_assign_global() {
+    let script_path = "./tests/data/constants/assign_global.rlox";
+    assert!(matches!(
+        Lox::new().run_file(script_path),
+        Err(Error::Semantic(_))
+    ));
+}
+
+#[test]
+fn test_compound_assign_local() {
+    let script_path = "./tests/data/constants/compound_assign_local.rlox";
+    assert!(matches!(
+        Lox::new().run_file(script_path),
+        Err(Error::Semantic(_))
+    ));
+}
+
+#[test]
+fn test_increment_captured() {
+    let script_path = "./tests/data/constants/increment_captured.rlox";
+    assert!(matches!(
+        Lox::new().run_file(script_path),
+        Err(Error::Semantic(_))
+    ));
+}
+
+#[test]
+fn test_assign_before_declaration() {
+    let script_path = "./tests/data/constants/assign_before_declaration.rlox";
+    match Lox::new().run_file(script_path) {
+        Err(err @ Error::RunTime(_)) => assert_eq!(
+            err.to_string(),
+            "[line 2] Runtime error: Can't assign to constant 'LIMIT'."
+        ),
+        other => panic!("expected a runtime error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_uninitialized() {
+    let script_path = "./tests/data/constants/uninitialized.rlox";
+    assert!(matches!(
+        Lox::new().run_file(script_path),
+        Err(Error::Syntactic(_))
+    ));
+}
+
+#[test]
+fn test_redeclare_global() {
+    let script_path = "./tests/data/constants/redeclare_global.rlox";
+    match Lox::new().run_file(script_path) {
+        Err(err @ Error::Semantic(_)) => assert_eq!(
+            err.to_string(),
+            "[line 2] Semantic error: Can't redeclare constant 'LIMIT'. at 'LIMIT'"
+        ),
+        other => panic!("expected a semantic error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_redeclare_by_import() {
+    let script_path = "./tests/data/constants/redeclare_by_import.rlox";
+    match Lox::new().run_file(script_path) {
+        Err(err @ Error::RunTime(_)) => assert_eq!(
+            err.to_string(),
+            "[line 2] Runtime error: Can't redeclare constant 'my_util'."
+        ),
+        other => panic!("expected a runtime error, got {:?}", other),
+    }
+}
@@ -0,0 +1,13 @@
+mod common;
+
+#[test]
+fn test_equality() {
+    let script_path = "./tests/data/equality.rlox";
+    assert_eq!(
+        common::run_file(script_path),
+        [
+            "true", "true", "true", "false", "true", "false", "true", "false", "true", "true",
+            "true", "false", "true",
+        ]
+    );
+}
@@ -0,0 +1,46 @@
+mod common;
+
+use rlox::error::Error;
+use rlox::lox::Lox;
+
+#[test]
+fn test_exceptions() {
+    let script_path = "./tests/data/exceptions.rlox";
+    assert_eq!(
+        common::run_file(script_path),
+        [
+            "caught: Age can't be negative.",
+            "Operands must be numbers.",
+            "14",
+            "Error instance",
+            "Undefined variable 'undefinedThing'.",
+            "Expected 2 arguments but got 1.",
+            "body",
+            "finally after success",
+            "inner finally",
+            "42",
+            "finally before return",
+            "returned",
+            "0",
+            "loop finally 0",
+            "loop finally 1",
+            "2",
+            "loop finally 2",
+            "first then second",
+            "true",
+            "custom error",
+        ]
+    );
+}
+
+#[test]
+fn test_uncaught_exception() {
+    let script_path = "./tests/data/uncaught_exception.rlox";
+    match Lox::new().run_file(script_path) {
+        Err(err @ Error::Throw { .. }) => assert_eq!(
+            err.to_string(),
+            "[line 3] Uncaught exception: the finally ran"
+        ),
+        other => panic!("expected an uncaught exception, got {:?}", other),
+    }
+}
@@ -1,5 +1,13 @@
+mod common;
+
 #[test]
 fn test_fibbonacci() {
     let script_path = "./tests/data/fibbonacci.rlox";
-    rlox::lox::Lox::new().run_file(script_path).unwrap();
+    assert_eq!(
+        common::run_file(script_path),
+        [
+            "0", "1", "1", "2", "3", "5", "8", "13", "21", "34", "55", "89", "144", "233", "377",
+            "610", "987", "1597", "2584", "4181", "6765",
+        ]
+    );
 }
@@ -0,0 +1,45 @@
+mod common;
+
+#[test]
+fn test_for_in() {
+    let script_path = "./tests/data/for_in.rlox";
+    assert_eq!(
+        common::run_file(script_path),
+        [
+            "1",
+            "2",
+            "3",
+            "alice is 25",
+            "bob is 30",
+            "h",
+            "é",
+            "l",
+            "l",
+            "o",
+            "👍🏽",
+            "🇫🇷",
+            "2",
+            "0",
+            "1",
+            "2",
+            "10",
+            "6",
+            "2",
+            "0",
+            "0.25",
+            "0.5",
+            "0.75",
+            "range(0, 5, 1)",
+            "true",
+            "25",
+            "[1, 2, 10, 20]",
+            "0",
+            "1",
+            "2",
+            "1",
+            "2",
+            "3",
+            "4",
+        ]
+    );
+}
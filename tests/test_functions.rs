@@ -0,0 +1,17 @@
+mod common;
+
+#[test]
+fn test_functions() {
+    let script_path = "./tests/data/functions.rlox";
+    assert_eq!(
+        common::run_file(script_path),
+        [
+            "Hi, Dear Reader!",
+            "1",
+            "2",
+            "3",
+            "<fn sayHi>",
+            "<native fn>",
+        ]
+    );
+}
@@ -0,0 +1,15 @@
+mod common;
+
+#[test]
+fn test_inheritance() {
+    let script_path = "./tests/data/inheritance.rlox";
+    assert_eq!(
+        common::run_file(script_path),
+        [
+            "Fry until golden brown.",
+            "Pipe full of custard and coat with chocolate.",
+            "boston cream doughnut",
+            "A method",
+        ]
+    );
+}
@@ -0,0 +1,21 @@
+mod common;
+
+#[test]
+fn test_interpolation() {
+    let script_path = "./tests/data/interpolation.rlox";
+    assert_eq!(
+        common::run_file(script_path),
+        [
+            "Hello Ada, you have 3 items",
+            "2",
+            "sum: 3!",
+            "nested: inner Ada",
+            "map: 1",
+            "list: [1, 2], bool: true, nil: nil",
+            "cost: ${not interpolated}",
+            "Hi there!",
+            "(1, 2) is a Point instance",
+            "multi 2 line",
+        ]
+    );
+}
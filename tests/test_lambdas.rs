@@ -0,0 +1,10 @@
+mod common;
+
+#[test]
+fn test_lambdas() {
+    let script_path = "./tests/data/lambdas.rlox";
+    assert_eq!(
+        common::run_file(script_path),
+        ["2", "4", "7", "<fn>", "15", "called right away", "0", "1"]
+    );
+}
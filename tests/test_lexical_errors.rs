@@ -0,0 +1,57 @@
+use rlox::error::Error;
+use rlox::lox::Lox;
+
+fn assert_lexical_error(script_path: &str, expected: &str) {
+    match Lox::new().run_file(script_path) {
+        Err(err @ Error::Lexical(_)) => assert_eq!(err.to_string(), expected),
+        other => panic!("expected a lexical error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_unterminated_string() {
+    assert_lexical_error(
+        "./tests/data/lexical_errors/unterminated_string.rlox",
+        "[line 2] Lexical error: Unterminated string.",
+    );
+}
+
+#[test]
+fn test_invalid_escape() {
+    assert_lexical_error(
+        "./tests/data/lexical_errors/invalid_escape.rlox",
+        "[line 1] Lexical error: Invalid escape sequence '\\q'.",
+    );
+}
+
+#[test]
+fn test_invalid_unicode_escape() {
+    assert_lexical_error(
+        "./tests/data/lexical_errors/invalid_unicode_escape.rlox",
+        "[line 1] Lexical error: Invalid unicode escape sequence.",
+    );
+}
+
+#[test]
+fn test_line_after_multiline_string() {
+    assert_lexical_error(
+        "./tests/data/lexical_errors/line_after_multiline_string.rlox",
+        "[line 3] Lexical error: Invalid escape sequence '\\x'.",
+    );
+}
+
+#[test]
+fn test_unterminated_comment() {
+    assert_lexical_error(
+        "./tests/data/lexical_errors/unterminated_comment.rlox",
+        "[line 2] Lexical error: Unterminated block comment.",
+    );
+}
+
+#[test]
+fn test_line_after_block_comment() {
+    assert_lexical_error(
+        "./tests/data/lexical_errors/line_after_block_comment.rlox",
+        "[line 4] Lexical error: Invalid escape sequence '\\x'.",
+    );
+}
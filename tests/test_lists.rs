@@ -0,0 +1,27 @@
+mod common;
+
+#[test]
+fn test_lists() {
+    let script_path = "./tests/data/lists.rlox";
+    assert_eq!(
+        common::run_file(script_path),
+        [
+            "[1, 2, 3]",
+            "4",
+            "[1, two, 3]",
+            "3",
+            "5",
+            "[1, two, 3, 4]",
+            "4",
+            "[0, 1, two, 3, end]",
+            "0",
+            "[30, 4]",
+            "[shared]",
+            "true",
+            "false",
+            "[1, [...]]",
+            "[[1, [...]]]",
+            "[[2], [2]]",
+        ]
+    );
+}
@@ -0,0 +1,10 @@
+mod common;
+
+#[test]
+fn test_loops() {
+    let script_path = "./tests/data/loops.rlox";
+    assert_eq!(
+        common::run_file(script_path),
+        ["0", "1", "3", "4", "3", "4", "0", "1", "2", "2"]
+    );
+}
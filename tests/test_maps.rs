@@ -0,0 +1,32 @@
+mod common;
+
+#[test]
+fn test_maps() {
+    let script_path = "./tests/data/maps.rlox";
+    assert_eq!(
+        common::run_file(script_path),
+        [
+            "{2: two, name: rlox, version: 1}",
+            "rlox",
+            "two",
+            "2",
+            "4",
+            "true",
+            "false",
+            "[2, debug, name, version]",
+            "[two, true, rlox, 2]",
+            "true",
+            "nil",
+            "{2: two, name: rlox, version: 2}",
+            "0",
+            "value",
+            "[10, 2]",
+            "still a block",
+            "true",
+            "false",
+            "{list: [{...}], self: {...}}",
+            "{list: [{...}], self: {...}}",
+            "{a: {x: 1}, b: {x: 1}}",
+        ]
+    );
+}
@@ -0,0 +1,112 @@
+mod common;
+
+use rlox::error::Error;
+use rlox::lox::Lox;
+
+#[test]
+fn test_modules() {
+    let script_path = "./tests/data/modules/main.rlox";
+    assert_eq!(
+        common::run_file(script_path),
+        [
+            "loading counter",
+            "<module greetings>",
+            "<Hello, modules>!",
+            "<Hello, Ada>!",
+            "!",
+            "2",
+            "<Hello, function>!",
+            "modul",
+            "5",
+            "alias",
+            "true",
+            "42",
+            "<module math-helpers>",
+            "still a name",
+        ]
+    );
+}
+
+#[test]
+fn test_import_cycle() {
+    let script_path = "./tests/data/modules/cycleA.rlox";
+    match Lox::new().run_file(script_path) {
+        Err(err @ Error::Import { .. }) => assert_eq!(
+            err.to_string(),
+            "[line 1] Runtime error: Import cycle: cycleA.rlox -> cycleB.rlox -> cycleA.rlox.\n    in module 'cycleB.rlox' imported on line 1"
+        ),
+        other => panic!("expected an import error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_missing_module() {
+    let script_path = "./tests/data/modules/missing_module.rlox";
+    assert!(matches!(
+        Lox::new().run_file(script_path),
+        Err(Error::RunTime(_))
+    ));
+}
+
+#[test]
+fn test_nested_import() {
+    let script_path = "./tests/data/modules/nested_import.rlox";
+    assert!(matches!(
+        Lox::new().run_file(script_path),
+        Err(Error::Semantic(_))
+    ));
+}
+
+#[test]
+fn test_unnamed_module() {
+    let script_path = "./tests/data/modules/unnamed_module.rlox";
+    match Lox::new().run_file(script_path) {
+        Err(err @ Error::RunTime(_)) => assert_eq!(
+            err.to_string(),
+            "[line 1] Runtime error: Module file name of 'lib/math-helpers.rlox' isn't a valid identifier, name it with 'as'."
+        ),
+        other => panic!("expected a runtime error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_module_builtins_not_exposed() {
+    let script_path = "./tests/data/modules/module_builtins.rlox";
+    match Lox::new().run_file(script_path) {
+        Err(err @ Error::RunTime(_)) => assert_eq!(
+            err.to_string(),
+            "[line 2] Runtime error: Undefined property 'len' in module 'my_util'."
+        ),
+        other => panic!("expected a runtime error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_syntax_error_in_module() {
+    let script_path = "./tests/data/modules/import_syntax_error.rlox";
+    match Lox::new().run_file(script_path) {
+        Err(err @ Error::Import { .. }) => {
+            assert!(matches!(err.cause(), Error::Syntactic(_)));
+            assert_eq!(
+                err.to_string(),
+                "[line 2] Syntactic error: Expect variable name at '='\n    in module 'lib/broken_syntax.rlox' imported on line 2"
+            );
+        }
+        other => panic!("expected an import error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_runtime_error_in_module() {
+    let script_path = "./tests/data/modules/import_runtime_error.rlox";
+    match Lox::new().run_file(script_path) {
+        Err(err @ Error::Import { .. }) => {
+            assert!(matches!(err.cause(), Error::RunTime(_)));
+            assert_eq!(
+                err.to_string(),
+                "[line 2] Runtime error: Operands must be numbers.\n    in module 'lib/broken_runtime.rlox' imported on line 2"
+            );
+        }
+        other => panic!("expected an import error, got {:?}", other),
+    }
+}
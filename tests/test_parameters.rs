@@ -0,0 +1,24 @@
+mod common;
+
+#[test]
+fn test_parameters() {
+    let script_path = "./tests/data/parameters.rlox";
+    assert_eq!(
+        common::run_file(script_path),
+        [
+            "Hello, Ada",
+            "Hi, Ada",
+            "[info] started []",
+            "[warn] slow [db, 250]",
+            "[0, 10, 5]",
+            "[0, 4, 2]",
+            "[0, 4, 1]",
+            "[1]",
+            "[2]",
+            "hey!!!",
+            "0",
+            "6",
+            "3, 0",
+        ]
+    );
+}
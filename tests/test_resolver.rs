@@ -0,0 +1,76 @@
+mod common;
+
+use rlox::error::Error;
+use rlox::lox::Lox;
+
+fn assert_semantic_error(script_path: &str, expected: &str) {
+    match Lox::new().run_file(script_path) {
+        Err(err @ Error::Semantic(_)) => assert_eq!(err.to_string(), expected),
+        other => panic!("expected a semantic error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_shadowing() {
+    let script_path = "./tests/data/resolver/shadowing.rlox";
+    assert_eq!(
+        common::run_file(script_path),
+        ["global", "global", "outer changed"]
+    );
+}
+
+#[test]
+fn test_own_initializer() {
+    assert_semantic_error(
+        "./tests/data/resolver/own_initializer.rlox",
+        "[line 3] Semantic error: Can't read local variable in its own initializer. at 'a'",
+    );
+}
+
+#[test]
+fn test_redeclaration() {
+    assert_semantic_error(
+        "./tests/data/resolver/redeclaration.rlox",
+        "[line 3] Semantic error: Already a variable with this name in this scope. at 'a'",
+    );
+}
+
+#[test]
+fn test_return_from_initializer() {
+    assert_semantic_error(
+        "./tests/data/resolver/return_from_initializer.rlox",
+        "[line 3] Semantic error: Can't return a value from an initializer. at 'return'",
+    );
+}
+
+#[test]
+fn test_this_outside_class() {
+    assert_semantic_error(
+        "./tests/data/resolver/this_outside_class.rlox",
+        "[line 2] Semantic error: Can't use 'this' outside of a class. at 'this'",
+    );
+}
+
+#[test]
+fn test_inherit_from_itself() {
+    assert_semantic_error(
+        "./tests/data/resolver/inherit_from_itself.rlox",
+        "[line 1] Semantic error: A class can't inherit from itself. at 'Oops'",
+    );
+}
+
+#[test]
+fn test_super_outside_class() {
+    assert_semantic_error(
+        "./tests/data/resolver/super_outside_class.rlox",
+        "[line 1] Semantic error: Can't use 'super' outside of a class. at 'super'",
+    );
+}
+
+#[test]
+fn test_super_without_superclass() {
+    assert_semantic_error(
+        "./tests/data/resolver/super_without_superclass.rlox",
+        "[line 3] Semantic error: Can't use 'super' in a class with no superclass. at 'super'",
+    );
+}
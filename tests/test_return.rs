@@ -0,0 +1,13 @@
+mod common;
+
+#[test]
+fn test_return() {
+    let script_path = "./tests/data/return.rlox";
+    assert_eq!(
+        common::run_file(script_path),
+        [
+            "0", "1", "1", "2", "3", "5", "8", "13", "21", "34", "55", "89", "144", "233", "377",
+            "610", "987", "1597", "2584", "4181", "6", "nil",
+        ]
+    );
+}
@@ -0,0 +1,146 @@
+use rlox::error::Error;
+use rlox::lox::Lox;
+use std::io;
+use std::io::Write;
+
+fn assert_runtime_error(script_path: &str) {
+    match Lox::new().run_file(script_path) {
+        Err(Error::RunTime(_)) => (),
+        other => panic!("expected a runtime error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_superclass_not_a_class() {
+    assert_runtime_error("./tests/data/runtime_errors/superclass_not_a_class.rlox");
+}
+
+#[test]
+fn test_negate_string() {
+    assert_runtime_error("./tests/data/runtime_errors/negate_string.rlox");
+}
+
+#[test]
+fn test_compare_string_number() {
+    assert_runtime_error("./tests/data/runtime_errors/compare_string_number.rlox");
+}
+
+#[test]
+fn test_subtract_nil() {
+    assert_runtime_error("./tests/data/runtime_errors/subtract_nil.rlox");
+}
+
+#[test]
+fn test_call_non_callable() {
+    assert_runtime_error("./tests/data/runtime_errors/call_non_callable.rlox");
+}
+
+#[test]
+fn test_undefined_variable() {
+    assert_runtime_error("./tests/data/runtime_errors/undefined_variable.rlox");
+}
+
+#[test]
+fn test_index_out_of_bounds() {
+    assert_runtime_error("./tests/data/runtime_errors/index_out_of_bounds.rlox");
+}
+
+#[test]
+fn test_insert_out_of_bounds() {
+    let script_path = "./tests/data/runtime_errors/insert_out_of_bounds.rlox";
+    match Lox::new().run_file(script_path) {
+        Err(err @ Error::RunTime(_)) => assert_eq!(
+            err.to_string(),
+            "[line 2] Runtime error: List index 3 out of bounds for length 2."
+        ),
+        other => panic!("expected a runtime error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_index_not_integer() {
+    assert_runtime_error("./tests/data/runtime_errors/index_not_integer.rlox");
+}
+
+#[test]
+fn test_pop_empty_list() {
+    assert_runtime_error("./tests/data/runtime_errors/pop_empty_list.rlox");
+}
+
+#[test]
+fn test_missing_map_key() {
+    assert_runtime_error("./tests/data/runtime_errors/missing_map_key.rlox");
+}
+
+#[test]
+fn test_invalid_map_key() {
+    assert_runtime_error("./tests/data/runtime_errors/invalid_map_key.rlox");
+}
+
+#[test]
+fn test_modulo_string() {
+    assert_runtime_error("./tests/data/runtime_errors/modulo_string.rlox");
+}
+
+#[test]
+fn test_too_few_arguments() {
+    assert_runtime_error("./tests/data/runtime_errors/too_few_arguments.rlox");
+}
+
+#[test]
+fn test_too_many_arguments() {
+    assert_runtime_error("./tests/data/runtime_errors/too_many_arguments.rlox");
+}
+
+#[test]
+fn test_missing_argument_before_rest() {
+    assert_runtime_error("./tests/data/runtime_errors/missing_argument_before_rest.rlox");
+}
+
+#[test]
+fn test_iterate_number() {
+    assert_runtime_error("./tests/data/runtime_errors/iterate_number.rlox");
+}
+
+#[test]
+fn test_range_zero_step() {
+    assert_runtime_error("./tests/data/runtime_errors/range_zero_step.rlox");
+}
+
+#[test]
+fn test_stack_overflow() {
+    // Runs on the test thread, whose stack is much smaller than the call
+    // depth limit needs
+    let script_path = "./tests/data/runtime_errors/stack_overflow.rlox";
+    match Lox::new().run_file(script_path) {
+        Err(err @ Error::RunTime(_)) => {
+            assert_eq!(err.to_string(), "[line 1] Runtime error: Stack overflow.")
+        }
+        other => panic!("expected a runtime error, got {:?}", other),
+    }
+}
+
+// Fails every write, like a pipe whose reader went away
+struct ClosedPipe;
+
+impl Write for ClosedPipe {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        Err(io::Error::from(io::ErrorKind::BrokenPipe))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_print_failure() {
+    let script_path = "./tests/data/runtime_errors/print_failure.rlox";
+    match Lox::with_output(ClosedPipe).run_file(script_path) {
+        Err(err @ Error::RunTime(_)) => assert_eq!(
+            err.to_string(),
+            "[line 2] Runtime error: Can't print: broken pipe."
+        ),
+        other => panic!("expected a runtime error, got {:?}", other),
+    }
+}
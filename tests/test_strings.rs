@@ -0,0 +1,22 @@
+mod common;
+
+#[test]
+fn test_strings() {
+    let script_path = "./tests/data/strings.rlox";
+    assert_eq!(
+        common::run_file(script_path),
+        [
+            "tab:\tend",
+            "line one",
+            "line two",
+            "she said \"hi\"",
+            "back\\slash",
+            "snowman: ☃, letter: A",
+            "héllo wörld",
+            "1",
+            "first",
+            "second",
+            "true",
+        ]
+    );
+}
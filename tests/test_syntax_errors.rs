@@ -0,0 +1,75 @@
+use rlox::error::Error;
+use rlox::lox::Lox;
+
+#[test]
+fn test_reports_every_error() {
+    let script_path = "./tests/data/syntax_errors/many.rlox";
+    match Lox::new().run_file(script_path) {
+        Err(err @ Error::Syntactic(_)) => {
+            let report = err.to_string();
+            let lines: Vec<&str> = report.lines().collect();
+            assert_eq!(
+                lines,
+                vec![
+                    "[line 1] Syntactic error: Expect expression. at ';'",
+                    "[line 3] Syntactic error: Expect ')' after expression. at ';'",
+                    "[line 5] Syntactic error: Expect ';' after variable declaration. at 'var'",
+                    "[line 6] Syntactic error: Invalid assignment target. at '='",
+                    "[line 7] Syntactic error: Can't return from top-level code. at 'return'",
+                ]
+            );
+        }
+        other => panic!("expected syntax errors, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_jump_outside_loop() {
+    let script_path = "./tests/data/syntax_errors/jump_outside_loop.rlox";
+    match Lox::new().run_file(script_path) {
+        Err(Error::Syntactic(errors)) => assert_eq!(errors.len(), 2),
+        other => panic!("expected syntax errors, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_invalid_update_target() {
+    let script_path = "./tests/data/syntax_errors/invalid_update_target.rlox";
+    match Lox::new().run_file(script_path) {
+        Err(Error::Syntactic(errors)) => assert_eq!(errors.len(), 2),
+        other => panic!("expected syntax errors, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_unclosed_interpolation() {
+    let script_path = "./tests/data/syntax_errors/unclosed_interpolation.rlox";
+    match Lox::new().run_file(script_path) {
+        Err(err @ Error::Syntactic(_)) => assert_eq!(
+            err.to_string(),
+            "[line 1] Syntactic error: Expect '}' after interpolated expression. at '2'"
+        ),
+        other => panic!("expected syntax errors, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_parameter_order() {
+    let script_path = "./tests/data/syntax_errors/parameters.rlox";
+    match Lox::new().run_file(script_path) {
+        Err(Error::Syntactic(errors)) => assert_eq!(errors.len(), 2),
+        other => panic!("expected syntax errors, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_top_level_return() {
+    let script_path = "./tests/data/syntax_errors/top_level_return.rlox";
+    match Lox::new().run_file(script_path) {
+        Err(err @ Error::Syntactic(_)) => assert_eq!(
+            err.to_string(),
+            "[line 2] Syntactic error: Can't return from top-level code. at 'return'"
+        ),
+        other => panic!("expected syntax errors, got {:?}", other),
+    }
+}